@@ -44,29 +44,79 @@ pub enum Code {
     Subtract,
     Multiply,
     Divide,
+    Modulo,
+    Power,
     Assign,
+
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+
+    Negate,
+    Not,
+    ToBool,
     
     Pop,
 
     PushNull,
-    PushNum(i32),
+    PushBool(bool),
+    PushNum(i64),
     PushFloat(f64),
     PushString(String),
     PushList(i32), // List size
-    PushListIndex, // Pop top num, and pop top list, take the index
+    PushMap(i32), // Amount of key value pairs
+    Concat(usize), // Pop the amount of values, push them joined as a string
+    JoinLists(usize), // Pop the amount of lists, push a list of all their items
+    PushListIndex, // Pop top num or key, and pop top list or map, take the index
+    PushSlice, // Pop end and start, both Ints or null, and pop top list or string, take the items in between
+    AssignIndex, // Pop top value, num or key and list or map, set the item at the index
+    Duplicate(usize), // Duplicate the amount of values on top of the stack
     PushVar(String),
+    Deref, // Pop top value, push the value of the variable it refers to
+    PushMember(String), // Pop top value, push its field or method with the name
+    PushRange, // Pop end and start, both Ints
+    PushIterator, // Pop top list or range, push an iterator over it
     PushFunction {
-        pars: Vec<String>,
+        pars: Vec<(String, bool)>, // Names, and whether the body declares a default when left out
+        rest: Option<String>,
         body_len: usize,
     },
 
     CallFunction {
-        args_len: usize
+        args_len: usize,
+        named: Vec<String> // Names of the last arguments
+    },
+    SpreadArguments(usize), // Pop the amount of named values and top list, push the list items, the named values and the total amount
+    EndArguments, // End of the arguments following a function call
+    Return, // Pop top value and return it from the function
+
+    Declare(String), // Pop top value, bind it in the current scope
+    DeclareConstant(String), // Pop top value, bind it in the current scope so that it can not be assigned to
+    AssignVar(String), // Pop top value, assign it to the variable in the scope defining it
+    Unpack { // Pop top list, push the rest as a list if wanted, and then the items with the first on top
+        count: usize,
+        rest: bool
     },
-    Return // Return from a function
+    PushScope,
+    PopScope,
+
+    IterNext(i32), // Advance the iterator on top and push the next item, jump if exhausted
+    EnterLoop, // Record the stack height, which break and continue return to
+    ExitLoop, // Forget the stack height recorded by the innermost loop
+    RestoreStack, // Drop the values pushed since entering the innermost loop
+    Jump(i32), // Relative to the next instruction
+    JumpIfDeclared(String, i32), // Jump if the variable is declared in the current scope
+    JumpIfFalse(i32), // Pop top value, jump if it is falsy
+
+    // Placeholders, replaced by jumps when the enclosing loop is compiled
+    Break,
+    Continue
 }
 
-#[allow(dead_code, non_camel_case_types)]
+#[allow(dead_code, non_camel_case_types, clippy::upper_case_acronyms)]
 #[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
 pub enum OPCode {
     NULL = 0x00,
@@ -36,11 +36,15 @@ impl Builder {
         self
     }
 
+    pub fn map(self, f: impl FnMut((usize, Instruction)) -> Instruction) -> Self {
+        Builder { list: self.list.into_iter().enumerate().map(f).collect() }
+    }
+
     pub fn into_iter(self) -> impl Iterator<Item = Instruction> {
         self.list.into_iter()
     }
 
-    pub fn to_vec(self) -> Vec<Instruction> {
+    pub fn into_vec(self) -> Vec<Instruction> {
         self.into_iter()
             .collect::<Vec<Instruction>>()
     }
@@ -50,7 +54,10 @@ impl Builder {
     }
 }
 
-pub struct Compiler { }
+pub struct Compiler {
+    loops: Vec<usize>, // Amount of scopes opened within each enclosing loop
+    function_depth: usize
+}
 
 #[allow(dead_code)]
 fn unimplemented(offset: usize, width: usize) -> Error {
@@ -62,42 +69,236 @@ fn unimplemented_expr(expr: &Expression) -> Error {
     unimplemented(expr.offset, expr.width)
 }
 
+fn leaves_value(declaration: &Declaration) -> bool {
+    match &declaration.declaration_type {
+        DeclarationType::Statement(statement) => match statement.statement_type {
+            StatementType::Expression(_) => !statement.end,
+            _ => false
+        },
+        DeclarationType::Variable { .. } => false
+    }
+}
+
+// Only blocks declaring variables get a scope of their own, others leave it to the VM to assign through them
+fn declares(ast: &AST) -> bool {
+    ast.iter().any(|declaration| matches!(declaration.declaration_type, DeclarationType::Variable { .. }))
+}
+
+impl Default for Compiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Compiler {
     pub fn new() -> Self {
-        Compiler {}
+        Compiler {
+            loops: Vec::new(),
+            function_depth: 0
+        }
     }
 
     fn declaration(&mut self, declaration: &Declaration) -> ProgramResult {
         match &declaration.declaration_type {
-            DeclarationType::Statement(statement) => self.statement(&statement)
+            DeclarationType::Statement(statement) => self.statement(statement),
+            DeclarationType::Variable { constant, pattern, value } => {
+                let instruction = |code| Instruction::new(declaration.offset, declaration.width, code);
+                let bind = if *constant { Code::DeclareConstant } else { Code::Declare };
+
+                Ok(match (pattern, value) {
+                    (pattern, Some(value)) => self.expression(value)?.append(self.pattern(pattern, value, bind)),
+                    (Pattern::Identifier(identifier), None) => Builder::from(instruction(Code::PushNull))
+                        .push_back(instruction(bind(String::from(*identifier)))),
+                    (_, None) => return Err(
+                        unimplemented(declaration.offset, declaration.width)
+                            .with_description(String::from("list patterns are always given a value by the parser"))
+                    )
+                })
+            }
         }
     }
 
     fn statement(&mut self, statement: &Statement) -> ProgramResult {
-        let mut stmt = match &statement.statement_type {
-            StatementType::Expression(expression) => self.expression(&expression)?
-        };
+        Ok(match &statement.statement_type {
+            StatementType::Expression(expression) => {
+                let stmt = self.expression(expression)?;
 
-        if statement.end {
-            stmt = stmt.push_back(Instruction::new(statement.offset, statement.width, Code::Pop));
-        }
+                if statement.end {
+                    stmt.push_back(Instruction::new(statement.offset, statement.width, Code::Pop))
+                } else {
+                    stmt
+                }
+            },
+            StatementType::While { condition, body } => {
+                let condition = self.expression(condition)?;
+                let scoped = declares(body);
+
+                self.loops.push(if scoped { 1 } else { 0 });
+                let body = self.discarded(body);
+                self.loops.pop();
+
+                let body = match scoped {
+                    true => Builder::from(Instruction::new(statement.offset, statement.width, Code::PushScope))
+                        .append(body?)
+                        .push_back(Instruction::new(statement.offset, statement.width, Code::PopScope)),
+                    false => body?
+                };
+                let (condition_len, body_len) = (condition.len() as i32, body.len() as i32);
+
+                // Resolve the breaks and continues belonging to this loop
+                let body = body.map(|(i, instruction)| match instruction.code {
+                    Code::Break => Instruction { code: Code::Jump(body_len - i as i32), ..instruction },
+                    Code::Continue => Instruction { code: Code::Jump(-(condition_len + i as i32 + 2)), ..instruction },
+                    _ => instruction
+                });
+
+                Builder::from(Instruction::new(statement.offset, statement.width, Code::EnterLoop))
+                    .append(condition)
+                    .push_back(Instruction::new(statement.offset, statement.width, Code::JumpIfFalse(body_len + 1))) // 1 is the Code::Jump
+                    .append(body)
+                    .push_back(Instruction::new(statement.offset, statement.width, Code::Jump(-(condition_len + body_len + 2))))
+                    .push_back(Instruction::new(statement.offset, statement.width, Code::ExitLoop))
+            },
+            StatementType::For { identifier, iterable, body } => {
+                let iterable = self.expression(iterable)?;
+
+                self.loops.push(1); // The scope holding the loop variable
+                let body = self.discarded(body);
+                self.loops.pop();
+
+                let body = body?;
+                let body_len = body.len() as i32;
+
+                let body = body.map(|(i, instruction)| match instruction.code {
+                    Code::Break => Instruction { code: Code::Jump(body_len - i as i32 + 1), ..instruction },
+                    Code::Continue => Instruction { code: Code::Jump(-(i as i32 + 4)), ..instruction },
+                    _ => instruction
+                });
+
+                let instruction = |code| Instruction::new(statement.offset, statement.width, code);
+
+                iterable
+                    .push_back(instruction(Code::PushIterator))
+                    .push_back(instruction(Code::EnterLoop)) // Breaking keeps the iterator on the stack
+                    .push_back(instruction(Code::IterNext(body_len + 4))) // Jump to the final Code::ExitLoop
+                    .push_back(instruction(Code::PushScope))
+                    .push_back(instruction(Code::Declare(String::from(*identifier))))
+                    .append(body)
+                    .push_back(instruction(Code::PopScope))
+                    .push_back(instruction(Code::Jump(-(body_len + 5))))
+                    .push_back(instruction(Code::ExitLoop))
+                    .push_back(instruction(Code::Pop)) // Pop the iterator
+            },
+            StatementType::Return(expr) => {
+                if self.function_depth == 0 {
+                    return Err(Error::new(statement.offset, statement.width, ErrorType::CompilerError(CompilerErrorType::ReturnOutsideFunction)));
+                }
+
+                match expr {
+                    Some(expr) => self.expression(expr)?,
+                    None => Builder::from(Instruction::new(statement.offset, statement.width, Code::PushNull))
+                }.push_back(Instruction::new(statement.offset, statement.width, Code::Return))
+            },
+            StatementType::Break | StatementType::Continue => {
+                let (code, error) = match statement.statement_type {
+                    StatementType::Break => (Code::Break, CompilerErrorType::BreakOutsideLoop),
+                    _ => (Code::Continue, CompilerErrorType::ContinueOutsideLoop)
+                };
+
+                let scopes = match self.loops.last() {
+                    Some(scopes) => *scopes,
+                    None => return Err(Error::new(statement.offset, statement.width, ErrorType::CompilerError(error)))
+                };
+
+                // Drop what the enclosing expression pushed and leave the scopes opened within the loop before jumping
+                let mut instructions = Builder::from(Instruction::new(statement.offset, statement.width, Code::RestoreStack));
+                for _ in 0..scopes {
+                    instructions = instructions.push_back(Instruction::new(statement.offset, statement.width, Code::PopScope));
+                }
 
-        Ok(stmt)
+                instructions.push_back(Instruction::new(statement.offset, statement.width, code))
+            }
+        })
     }
 
     fn expression(&mut self, expr: &Expression) -> ProgramResult {
         Ok(match &expr.expression_type {
             ExpressionType::Primary(primary) => match primary {
                 Primary::Literal(literal) => {
-                    Builder::from(Instruction::from_expression(&expr, match literal {
+                    Builder::from(Instruction::from_expression(expr, match literal {
                         Literal::Null => Code::PushNull,
+                        Literal::Bool(b) => Code::PushBool(*b),
                         Literal::Int(i) => Code::PushNum(*i),
                         Literal::Float(f) => Code::PushFloat(*f),
-                        Literal::String(s) => Code::PushString(String::from(s))
+                        Literal::String(s) => Code::PushString(String::from(s)),
+                        Literal::Interpolated(_) => return Err(
+                            unimplemented_expr(expr)
+                                .with_description(String::from("interpolated strings are parsed as ExpressionType::Interpolation"))
+                        )
                     }))
                 },
                 Primary::Identifier(identifier) => {
-                    Builder::from(Instruction::from_expression(&expr, Code::PushVar(String::from(*identifier))))
+                    Builder::from(Instruction::from_expression(expr, Code::PushVar(String::from(*identifier))))
+                }
+            },
+            ExpressionType::Binary {left, right, operator: Token::DoubleAmpersand, offset, width} => {
+                let right = self.expression(right)?;
+                let right_len = right.len() as i32;
+
+                // Skip the right hand side if the left hand side is false
+                self.expression(left)?
+                    .push_back(Instruction::new(*offset, *width, Code::JumpIfFalse(right_len + 2)))
+                    .append(right)
+                    .push_back(Instruction::new(*offset, *width, Code::ToBool))
+                    .push_back(Instruction::new(*offset, *width, Code::Jump(1)))
+                    .push_back(Instruction::new(*offset, *width, Code::PushBool(false)))
+            },
+            ExpressionType::Binary {left, right, operator: Token::DoublePipe, offset, width} => {
+                let right = self.expression(right)?;
+                let right_len = right.len() as i32;
+
+                // Skip the right hand side if the left hand side is true
+                self.expression(left)?
+                    .push_back(Instruction::new(*offset, *width, Code::JumpIfFalse(2)))
+                    .push_back(Instruction::new(*offset, *width, Code::PushBool(true)))
+                    .push_back(Instruction::new(*offset, *width, Code::Jump(right_len + 1)))
+                    .append(right)
+                    .push_back(Instruction::new(*offset, *width, Code::ToBool))
+            },
+            ExpressionType::Binary {left, right, operator: operator @ (
+                Token::PlusEquals |
+                Token::MinusEquals |
+                Token::AsterixEquals |
+                Token::FSlashEquals |
+                Token::PercentEquals |
+                Token::DoubleAsterixEquals
+            ), offset, width} => {
+                let code = match operator {
+                    Token::PlusEquals => Code::Add,
+                    Token::MinusEquals => Code::Subtract,
+                    Token::AsterixEquals => Code::Multiply,
+                    Token::FSlashEquals => Code::Divide,
+                    Token::PercentEquals => Code::Modulo,
+                    _ => Code::Power
+                };
+
+                // The target is only evaluated once, and then duplicated to be read from
+                match self.index_target(left)? {
+                    Some(target) => {
+                        target
+                            .push_back(Instruction::new(*offset, *width, Code::Duplicate(2)))
+                            .push_back(Instruction::from_expression(left, Code::PushListIndex))
+                            .append(self.expression(right)?)
+                            .push_back(Instruction::new(*offset, *width, code))
+                            .push_back(Instruction::new(*offset, *width, Code::AssignIndex))
+                    },
+                    None => {
+                        self.expression(left)?
+                            .push_back(Instruction::new(*offset, *width, Code::Duplicate(1)))
+                            .append(self.expression(right)?)
+                            .push_back(Instruction::new(*offset, *width, code))
+                            .push_back(Instruction::new(*offset, *width, Code::Assign))
+                    }
                 }
             },
             ExpressionType::Binary {left, right, operator, offset, width} => {
@@ -106,85 +307,347 @@ impl Compiler {
                     Token::Minus => Code::Subtract,
                     Token::FSlash => Code::Divide,
                     Token::Asterix => Code::Multiply,
+                    Token::Percent => Code::Modulo,
+                    Token::DoubleAsterix => Code::Power,
                     Token::Equals => Code::Assign,
+                    Token::DoubleDot => Code::PushRange,
+                    Token::DoubleEquals => Code::Equal,
+                    Token::NotEquals => Code::NotEqual,
+                    Token::Less => Code::Less,
+                    Token::LessEquals => Code::LessEqual,
+                    Token::Greater => Code::Greater,
+                    Token::GreaterEquals => Code::GreaterEqual,
                     _ => return Err(
                         unimplemented(*offset, *width)
                             .with_description(format!("unimplemented operator {:?}", operator))
                     )
                 };
 
+                // Assigning to a list item or member needs the container and the index, rather than the item itself
+                let (left, code) = match (code, self.index_target(left)?) {
+                    (Code::Assign, Some(target)) => (target, Code::AssignIndex),
+                    (code, _) => (self.expression(left)?, code)
+                };
+
                 Builder::new()
-                    .append(self.expression(&*left)?)
-                    .append(self.expression(&*right)?)
+                    .append(left)
+                    .append(self.expression(right)?)
                     .push_back(Instruction::new(*offset, *width, code))
             },
-            ExpressionType::Function {pars, body} => {
-                let body = self.get_compiled(body)?;
+            ExpressionType::Unary { expr, operator, offset, width } => {
+                let code = match operator {
+                    Token::Minus => Code::Negate,
+                    Token::Exclamation => Code::Not,
+                    _ => return Err(
+                        unimplemented(*offset, *width)
+                            .with_description(format!("unimplemented operator {:?}", operator))
+                    )
+                };
+
+                self.expression(expr)?
+                    .push_back(Instruction::new(*offset, *width, code))
+            },
+            ExpressionType::Function {pars, rest, body} => {
+                // Loops outside of the function can not be broken from within it, not even from a default
+                let loops = std::mem::take(&mut self.loops);
+                self.function_depth += 1;
+                let compiled = self.parameters(pars, expr)
+                    .and_then(|(names, prologue)| Ok((names, prologue.append(self.block(body, expr)?))));
+                self.function_depth -= 1;
+                self.loops = loops;
+
+                let (names, body) = compiled?;
 
-                Builder::from(Instruction::from_expression(&expr, Code::PushFunction {
-                    pars: pars.into_iter()
-                        .map(|v| String::from(*v))
-                        .collect::<Vec<String>>(),
+                Builder::from(Instruction::from_expression(expr, Code::PushFunction {
+                    pars: names,
+                    rest: rest.map(String::from),
                     body_len: body.len() + 1 // 1 is the Code::Return
                 }))
                 .append(body)
-                .push_back(Instruction::from_expression(&expr, Code::Return))
-
-            },
-            ExpressionType::FunctionCall { func, args } => {
-                let args = Builder::new()
-                    .append({
-                        let mut instructions = Builder::new();
-                        for arg in args {
-                            instructions = instructions.append(self.expression(&*arg)?);
-                        }
-                        
-                        // Push back amount of arguments
-                        instructions.push_back(Instruction::from_expression(&expr, Code::PushNum(args.len() as i32)))
-                    })
-                    .push_back(Instruction::from_expression(&expr, Code::Return));
-
-
-                self.expression(&*func)?
-                    .push_back(Instruction::from_expression(&expr, Code::CallFunction {
-                        args_len: args.len()
+                .push_back(Instruction::from_expression(expr, Code::Return))
+
+            },
+            ExpressionType::FunctionCall { func, args, named } => {
+                let spread = args.iter().any(|arg| matches!(arg.expression_type, ExpressionType::Spread(_)));
+
+                // Arguments are evaluated apart from the loop the call is in, so they can not break out of it
+                let loops = std::mem::take(&mut self.loops);
+                let arguments = self.arguments(args, named, spread, expr);
+                self.loops = loops;
+                let arguments = arguments?;
+
+                self.expression(func)?
+                    .push_back(Instruction::from_expression(expr, Code::CallFunction {
+                        args_len: arguments.len(),
+                        named: named.iter()
+                            .map(|(name, _)| String::from(*name))
+                            .collect()
                     }))
-                    .append(args)
+                    .append(arguments)
             },
-            ExpressionType::List(list) => {
-                Builder::new()
-                    .append({
-                        let mut instructions = Builder::new();
-                        for item in list {
-                            instructions = instructions.append(self.expression(&*item)?);
-                        }
+            ExpressionType::List(list) => self.list(list, expr)?,
+            ExpressionType::Interpolation(parts) => {
+                let mut instructions = Builder::new();
+                for part in parts {
+                    instructions = instructions.append(self.expression(part)?);
+                }
 
-                        instructions
-                    })
-                    .push_back(Instruction::from_expression(&expr, Code::PushList(list.len() as i32)))
+                instructions.push_back(Instruction::from_expression(expr, Code::Concat(parts.len())))
+            },
+            ExpressionType::Map(pairs) => {
+                let mut instructions = Builder::new();
+                for (key, value) in pairs {
+                    instructions = instructions
+                        .push_back(Instruction::from_expression(value, Code::PushString(String::from(*key))))
+                        .append(self.expression(value)?);
+                }
+
+                instructions.push_back(Instruction::from_expression(expr, Code::PushMap(pairs.len() as i32)))
             },
             ExpressionType::ListIndex { list, index } => {
                 Builder::new()
                     .append(self.expression(list)?)
                     .append(self.expression(index)?)
-                    .push_back(Instruction::from_expression(&expr, Code::PushListIndex))
+                    .push_back(Instruction::from_expression(expr, Code::PushListIndex))
+            },
+            ExpressionType::Slice { list, start, end } => {
+                // Left out bounds are pushed as null
+                let mut instructions = self.expression(list)?;
+                for bound in [start, end].iter() {
+                    instructions = match bound {
+                        Some(bound) => instructions.append(self.expression(bound)?),
+                        None => instructions.push_back(Instruction::from_expression(expr, Code::PushNull))
+                    };
+                }
+
+                instructions.push_back(Instruction::from_expression(expr, Code::PushSlice))
+            },
+            ExpressionType::Member { object, name } => {
+                self.expression(object)?
+                    .push_back(Instruction::from_expression(expr, Code::PushMember(String::from(*name))))
+            },
+            ExpressionType::Destructure { pattern, value } => {
+                // The assigned value is kept as the value of the expression
+                self.expression(value)?
+                    .push_back(Instruction::from_expression(expr, Code::Duplicate(1)))
+                    .append(self.pattern(pattern, expr, Code::AssignVar))
+            },
+            ExpressionType::Spread(_) => return Err(
+                unimplemented_expr(expr)
+                    .with_description(String::from("spreading is only supported in lists and function arguments"))
+            ),
+            ExpressionType::Block(ast) if declares(ast) => {
+                // Breaking out of the block from within a loop leaves its scope too
+                if let Some(scopes) = self.loops.last_mut() {
+                    *scopes += 1;
+                }
+
+                let block = self.block(ast, expr);
+
+                if let Some(scopes) = self.loops.last_mut() {
+                    *scopes -= 1;
+                }
+
+                // The value is looked up while the names the block declared are still in scope
+                Builder::from(Instruction::from_expression(expr, Code::PushScope))
+                    .append(block?)
+                    .push_back(Instruction::from_expression(expr, Code::Deref))
+                    .push_back(Instruction::from_expression(expr, Code::PopScope))
+            },
+            ExpressionType::Block(ast) => self.block(ast, expr)?,
+            ExpressionType::If { condition, body, else_body } => {
+                let body = self.expression(body)?;
+                let else_body = match else_body {
+                    Some(else_body) => self.expression(else_body)?,
+                    None => Builder::from(Instruction::from_expression(expr, Code::PushNull))
+                };
+
+                self.expression(condition)?
+                    .push_back(Instruction::from_expression(expr, Code::JumpIfFalse(body.len() as i32 + 1))) // 1 is the Code::Jump
+                    .append(body)
+                    .push_back(Instruction::from_expression(expr, Code::Jump(else_body.len() as i32)))
+                    .append(else_body)
             },
             ExpressionType::Empty => Builder::new(),
             // _ => return Err(unimplemented_expr(&expr))
         })
     }
 
+    // Compiles the prologue of a function body, returning it with the names of the parameters.
+    // Defaults of left out arguments are evaluated first thing in the body, in the order of the parameters.
+    // Destructured parameters are passed under a name no identifier can have, and unpacked after their default
+    fn parameters(&mut self, pars: &[Parameter], expr: &Expression) -> Result<(Vec<(String, bool)>, Builder), Error> {
+        let mut names = Vec::new();
+        let mut prologue = Builder::new();
+        for (i, par) in pars.iter().enumerate() {
+            let name = match par.pattern {
+                Pattern::Identifier(identifier) => String::from(identifier),
+                _ => format!("<parameter {}>", i)
+            };
+
+            if let Some(default) = &par.default {
+                let default = self.expression(default)?;
+
+                prologue = prologue
+                    .push_back(Instruction::from_expression(expr, Code::JumpIfDeclared(name.clone(), default.len() as i32 + 1)))
+                    .append(default)
+                    .push_back(Instruction::from_expression(expr, Code::Declare(name.clone())));
+            }
+
+            if let Pattern::List { .. } = par.pattern {
+                prologue = prologue
+                    .push_back(Instruction::from_expression(expr, Code::PushVar(name.clone())))
+                    .append(self.pattern(&par.pattern, expr, Code::Declare));
+            }
+
+            names.push((name, par.default.is_some()));
+        }
+
+        Ok((names, prologue))
+    }
+
+    // Compiles the arguments of a call, followed by their amount
+    fn arguments(&mut self, args: &[Box<Expression>], named: &[(&str, Box<Expression>)], spread: bool, expr: &Expression) -> ProgramResult {
+        // With spread arguments the amount is only known at runtime, so they are gathered in a list first
+        let mut instructions = if spread {
+            self.list(args, expr)?
+        } else {
+            let mut instructions = Builder::new();
+            for arg in args {
+                instructions = instructions.append(self.expression(arg)?);
+            }
+
+            instructions
+        };
+
+        // Named arguments follow the positional ones
+        for (_, arg) in named {
+            instructions = instructions.append(self.expression(arg)?);
+        }
+
+        // Push back amount of arguments
+        Ok(instructions
+            .push_back(Instruction::from_expression(expr, if spread {
+                Code::SpreadArguments(named.len())
+            } else {
+                Code::PushNum((args.len() + named.len()) as i64)
+            }))
+            .push_back(Instruction::from_expression(expr, Code::EndArguments)))
+    }
+
+    // Compiles the items of a list, flattening spread items at runtime
+    fn list(&mut self, items: &[Box<Expression>], expr: &Expression) -> Result<Builder, Error> {
+        let mut instructions = Builder::new();
+        let mut plain = 0;
+        let mut lists = 0;
+
+        for item in items {
+            if let ExpressionType::Spread(list) = &item.expression_type {
+                if plain > 0 {
+                    instructions = instructions.push_back(Instruction::from_expression(expr, Code::PushList(plain)));
+                    lists += 1;
+                    plain = 0;
+                }
+
+                // Joining a single list checks that the spread value is a list, and copies it
+                instructions = instructions
+                    .append(self.expression(list)?)
+                    .push_back(Instruction::from_expression(item, Code::JoinLists(1)));
+                lists += 1;
+            } else {
+                instructions = instructions.append(self.expression(item)?);
+                plain += 1;
+            }
+        }
+
+        if lists == 0 {
+            return Ok(instructions.push_back(Instruction::from_expression(expr, Code::PushList(plain))));
+        }
+
+        if plain > 0 {
+            instructions = instructions.push_back(Instruction::from_expression(expr, Code::PushList(plain)));
+            lists += 1;
+        }
+
+        Ok(instructions.push_back(Instruction::from_expression(expr, Code::JoinLists(lists))))
+    }
+
+    // Compiles the container and index of an assignment target, if it is a list item or a member
+    fn index_target(&mut self, target: &Expression) -> Result<Option<Builder>, Error> {
+        Ok(match &target.expression_type {
+            ExpressionType::ListIndex { list, index } => Some(
+                self.expression(list)?.append(self.expression(index)?)
+            ),
+            ExpressionType::Member { object, name } => Some(
+                self.expression(object)?
+                    .push_back(Instruction::from_expression(target, Code::PushString(String::from(*name))))
+            ),
+            _ => None
+        })
+    }
+
+    // Binds the value on top of the stack to the pattern, declaring or assigning each name with the given instruction
+    fn pattern(&mut self, pattern: &Pattern, expr: &Expression, bind: fn(String) -> Code) -> Builder {
+        let instruction = |name: &str| Instruction::from_expression(expr, bind(String::from(name)));
+
+        match pattern {
+            Pattern::Identifier(identifier) => Builder::from(instruction(identifier)),
+            Pattern::List { items, rest, offset, width } => {
+                let mut instructions = Builder::from(Instruction::new(*offset, *width, Code::Unpack {
+                    count: items.len(),
+                    rest: rest.is_some()
+                }));
+
+                for item in items {
+                    instructions = instructions.append(self.pattern(item, expr, bind));
+                }
+
+                match rest {
+                    Some(rest) => instructions.push_back(instruction(rest)),
+                    None => instructions
+                }
+            }
+        }
+    }
+
+    // Compiles a block so that it always leaves exactly one value on the stack
+    fn block(&mut self, ast: &AST, expr: &Expression) -> ProgramResult {
+        let program = self.get_compiled(ast)?;
+
+        Ok(match ast.last() {
+            Some(declaration) if leaves_value(declaration) => program,
+            _ => program.push_back(Instruction::from_expression(expr, Code::PushNull))
+        })
+    }
+
+    // Compiles a block whose value is not used
+    fn discarded(&mut self, ast: &AST) -> ProgramResult {
+        let program = self.get_compiled(ast)?;
+
+        Ok(match ast.last() {
+            Some(declaration) if leaves_value(declaration) => {
+                program.push_back(Instruction::new(declaration.offset, declaration.width, Code::Pop))
+            },
+            _ => program
+        })
+    }
+
     fn get_compiled(&mut self, ast: &AST) -> ProgramResult {
         let mut program = Builder::new();
 
-        for declaration in ast {
-            program = program.append(self.declaration(&declaration)?);
+        for (i, declaration) in ast.iter().enumerate() {
+            program = program.append(self.declaration(declaration)?);
+
+            // Only the last declaration may leave its value on the stack
+            if i + 1 < ast.len() && leaves_value(declaration) {
+                program = program.push_back(Instruction::new(declaration.offset, declaration.width, Code::Pop));
+            }
         }
 
         Ok(program)
     }
 
     pub fn compile(&mut self, ast: &AST) -> Result<Program, Error> {
-        Ok(self.get_compiled(ast)?.to_vec())
+        Ok(self.get_compiled(ast)?.into_vec())
     }
 }
\ No newline at end of file
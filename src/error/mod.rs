@@ -3,6 +3,9 @@ use std::fmt;
 #[derive(Debug)]
 pub enum LexerErrorType {
     UnexpectedEndOfString,
+    InvalidEscapeSequence,
+    UnterminatedComment,
+    MalformedNumber,
     UnknownToken
 }
 
@@ -14,12 +17,30 @@ pub enum ParserErrorType {
     UnexpectedToken,
     UnclosedParenthesis,
     UnclosedBrace,
-    UnclosedBracket
+    UnclosedBracket,
+    ExpectedBlock,
+    InvalidPattern
+}
+
+#[derive(Debug)]
+pub enum ResolverErrorType {
+    AssignToConstant {
+        identifier: String
+    },
+    RedeclaredConstant {
+        identifier: String
+    },
+    UndeclaredVariable {
+        identifier: String
+    }
 }
 
 #[derive(Debug)]
 pub enum CompilerErrorType {
-    NotImplemented
+    NotImplemented,
+    BreakOutsideLoop,
+    ContinueOutsideLoop,
+    ReturnOutsideFunction
 }
 
 #[derive(Debug)]
@@ -32,10 +53,38 @@ pub enum VMErrorType {
     OperationNotSupported,
     AssignToNonVariable,
     IndexOutOfBounds {
-        list_size: i32,
-        index: i32
+        list_size: i64,
+        index: i64
+    },
+    KeyNotFound {
+        key: String
+    },
+    UnknownMember {
+        member: String
     },
     MismatchedArgumentCount,
+    MissingArgument {
+        parameter: String
+    },
+    UnexpectedArgument {
+        index: usize // Position of the positional argument without a parameter
+    },
+    UnknownParameter {
+        parameter: String
+    },
+    DuplicateArgument {
+        parameter: String
+    },
+    MismatchedPatternLength {
+        expected: usize,
+        found: usize
+    },
+    AssignToConstant {
+        identifier: String
+    },
+    IntegerOverflow,
+    DivisionByZero,
+    StringTooLarge,
     StackOverflow {
         stack_size: usize,
         index: i32
@@ -46,6 +95,7 @@ pub enum VMErrorType {
 pub enum ErrorType {
     LexerError(LexerErrorType),
     ParserError(ParserErrorType),
+    ResolverError(ResolverErrorType),
     CompilerError(CompilerErrorType),
     VMError(VMErrorType),
     Unknown
@@ -77,22 +127,22 @@ impl Error {
 
     pub fn with_code(mut self, code: String) -> Self {
         self.code = Some(code);
-        return self;
+        self
     }
 
     pub fn with_file(mut self, file: String) -> Self {
         self.file = Some(file);
-        return self;
+        self
     }
 
     pub fn with_help(mut self, help: String) -> Self {
         self.help = Some(help);
-        return self;
+        self
     }
 
     pub fn with_description(mut self, description: String) -> Self {
         self.description = Some(description);
-        return self;
+        self
     }
 }
 
@@ -112,11 +162,10 @@ fn get_line_pos(code: &str, pos: usize) -> (usize, usize, usize, usize) {
     let mut indents = 0;
     let mut line_indents = 0;
 
-    let chars = code.as_bytes();
     let len = code.len();
 
-    for i in 0..len {
-        if chars[i] == '\n' as u8 {
+    for (i, c) in code.bytes().enumerate() {
+        if c == b'\n' {
             if i >= pos || i >= len - 1 {
                 break;
             }
@@ -138,7 +187,7 @@ fn get_line_pos(code: &str, pos: usize) -> (usize, usize, usize, usize) {
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        if let None = self.code {
+        if self.code.is_none() {
             return write!(f, "No code supplied for error")
         }
 
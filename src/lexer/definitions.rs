@@ -21,11 +21,24 @@ pub enum Token {
     Plus,
     Asterix,
     DoubleAsterix,
+    Percent,
     Minus,
     Equals,
     DoubleEquals,
+    NotEquals,
+    Less,
+    LessEquals,
+    Greater,
+    GreaterEquals,
+    DoubleAmpersand,
+    DoublePipe,
+    Exclamation,
     MinusEquals,
     PlusEquals,
+    AsterixEquals,
+    FSlashEquals,
+    PercentEquals,
+    DoubleAsterixEquals,
     ParClosed,
     ParOpen,
     BracketClosed,
@@ -33,9 +46,23 @@ pub enum Token {
     BraceClosed,
     BraceOpen,
     SemiColon,
+    Colon,
     Comma,
     Dot,
-    Lambda
+    DoubleDot,
+    TripleDot,
+    Lambda,
+
+    If,
+    Else,
+    While,
+    Break,
+    Continue,
+    For,
+    In,
+    Return,
+    Let,
+    Const
 }
 
 lazy_static! {
@@ -48,11 +75,24 @@ lazy_static! {
         "+" => Plus,
         "*" => Asterix,
         "**" => DoubleAsterix,
+        "%" => Percent,
         "-" => Minus,
         "=" => Equals,
         "==" => DoubleEquals,
+        "!=" => NotEquals,
+        "<" => Less,
+        "<=" => LessEquals,
+        ">" => Greater,
+        ">=" => GreaterEquals,
+        "&&" => DoubleAmpersand,
+        "||" => DoublePipe,
+        "!" => Exclamation,
         "-=" => MinusEquals,
         "+=" => PlusEquals,
+        "*=" => AsterixEquals,
+        "/=" => FSlashEquals,
+        "%=" => PercentEquals,
+        "**=" => DoubleAsterixEquals,
         "(" => ParOpen,
         ")" => ParClosed,
         "{" => BracketOpen,
@@ -60,8 +100,24 @@ lazy_static! {
         "[" => BraceOpen,
         "]" => BraceClosed,
         ";" => SemiColon,
+        ":" => Colon,
         "," => Comma,
         "." => Dot,
+        ".." => DoubleDot,
+        "..." => TripleDot,
         "=>" => Lambda
     };
+
+    pub static ref KEYWORDS: Definition<Token> = hashmap!{
+        "if" => If,
+        "else" => Else,
+        "while" => While,
+        "break" => Break,
+        "continue" => Continue,
+        "for" => For,
+        "in" => In,
+        "return" => Return,
+        "let" => Let,
+        "const" => Const
+    };
 }
\ No newline at end of file
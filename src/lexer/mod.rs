@@ -23,11 +23,27 @@ pub enum BlockType {
 #[derive(Debug)]
 pub enum Literal {
     Null,
+    Bool(bool),
     String(String),
-    Int(i32),
+    Interpolated(Vec<StringPart>), // A string with embedded expressions, like "Hello ${name}"
+    Int(i64),
     Float(f64)
 }
 
+#[derive(Debug)]
+pub enum StringPart {
+    Text {
+        literal: Literal, // Always a Literal::String
+        offset: usize,
+        width: usize
+    },
+    Code {
+        blocks: LinkedList<Block>,
+        offset: usize,
+        width: usize
+    }
+}
+
 #[derive(Debug)]
 pub struct Block {
     pub block_type: BlockType,
@@ -49,9 +65,111 @@ impl Block {
     }
 }
 
-fn get_last(positions: &Vec<usize>) -> usize {
+fn get_last(positions: &[usize]) -> usize {
     let len = positions.len();
-    if len <= 0 { 0 } else { *positions.get(len - 1).or(Some(&0)).unwrap() }
+    if len == 0 { 0 } else { *positions.get(len - 1).unwrap_or(&0) }
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+// Replaces the escape sequences of a string literal starting at offset
+fn unescape(content: &str, offset: usize) -> Result<String, Error> {
+    let chars: Vec<char> = content.chars().collect();
+    let mut result = String::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        if chars[i] != '\\' {
+            result.push(chars[i]);
+            i += 1;
+            continue;
+        }
+
+        let start = i;
+        i += 2; // The backslash and the escaped character
+
+        let escaped = match chars.get(start + 1) {
+            Some('n') => Some('\n'),
+            Some('t') => Some('\t'),
+            Some('r') => Some('\r'),
+            Some('0') => Some('\0'),
+            Some('\\') => Some('\\'),
+            Some('"') => Some('"'),
+            Some('$') => Some('$'),
+            Some('u') if chars.get(i) == Some(&'{') => {
+                let digits: String = chars[i + 1..].iter().take_while(|c| c.is_ascii_hexdigit()).collect();
+                i += digits.len() + 1;
+
+                if chars.get(i) == Some(&'}') && !digits.is_empty() && digits.len() <= 6 {
+                    i += 1;
+                    u32::from_str_radix(&digits, 16).ok().and_then(std::char::from_u32)
+                } else {
+                    i += 1; // Include the offending character
+                    None
+                }
+            },
+            _ => None
+        };
+
+        match escaped {
+            Some(c) => result.push(c),
+            None => return Err(
+                Error::new(offset + start, i.min(chars.len()) - start, ErrorType::LexerError(LexerErrorType::InvalidEscapeSequence))
+                    .with_help(String::from("valid escapes are \\n, \\t, \\r, \\0, \\\\, \\\", \\$ and \\u{...}"))
+            )
+        }
+    }
+
+    Ok(result)
+}
+
+// The position of the } closing an interpolation whose code starts at from
+fn interpolation_end(chars: &[char], from: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut i = from;
+
+    while i < chars.len() {
+        match chars[i] {
+            '{' => depth += 1,
+            '}' if depth == 0 => return Some(i),
+            '}' => depth -= 1,
+            '"' => i = string_end(chars, i + 1, opens_raw(chars, i))?,
+            _ => {}
+        }
+        i += 1;
+    }
+
+    None
+}
+
+// The position of the quotation mark closing a string whose content starts at from.
+// Raw strings have neither escapes nor interpolations
+fn string_end(chars: &[char], from: usize, raw: bool) -> Option<usize> {
+    let mut i = from;
+
+    while i < chars.len() {
+        match chars[i] {
+            '\\' if !raw => i += 1,
+            '"' => return Some(i),
+            '$' if !raw && chars.get(i + 1) == Some(&'{') => i = interpolation_end(chars, i + 2)?,
+            _ => {}
+        }
+        i += 1;
+    }
+
+    None
+}
+
+// Whether the quotation mark at the index opens a raw string, like r"..."
+fn opens_raw(chars: &[char], i: usize) -> bool {
+    i > 0 && chars[i - 1] == 'r' && (i < 2 || !is_identifier_char(chars[i - 2]))
+}
+
+fn unclosed_interpolation(offset: usize) -> Error {
+    Error::new(offset, 2, ErrorType::LexerError(LexerErrorType::UnexpectedEndOfString))
+        .with_help(String::from("unclosed interpolation"))
 }
 
 pub struct Lexer {
@@ -59,13 +177,19 @@ pub struct Lexer {
     identifier_re: Regex
 }
 
+impl Default for Lexer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Lexer {
     pub fn new() -> Self {
         let mut tokens = TOKENS.iter()
-            .map(|(k, v)| (String::from(*k), v.clone()))
+            .map(|(k, v)| (String::from(*k), *v))
             .collect::<Vec<(String, Token)>>();
             
-        tokens.sort_by(|a, b| b.0.len().cmp(&a.0.len()));
+        tokens.sort_by_key(|v| std::cmp::Reverse(v.0.len()));
 
         Lexer {
             tokens,
@@ -93,8 +217,8 @@ impl Lexer {
             let slice = &content[..symbol.len()];
             if *slice == **symbol {
                 return Some(Block::new(
-                    BlockType::Token(token.clone()),
-                    token.clone(),
+                    BlockType::Token(*token),
+                    *token,
                     String::from(slice),
                     offset
                 ));
@@ -110,6 +234,15 @@ impl Lexer {
         while i > 0 {
             let slice = &content[..i];
             if self.identifier_re.is_match(slice) {
+                if let Some(keyword) = KEYWORDS.get(slice) {
+                    return Some(Block::new(
+                        BlockType::Token(*keyword),
+                        *keyword,
+                        String::from(slice),
+                        offset
+                    ));
+                }
+
                 return Some(Block::new(
                     BlockType::Identifier(String::from(slice)),
                     Token::Identifier,
@@ -123,37 +256,93 @@ impl Lexer {
         None
     }
 
-    fn get_literal(&self, content: &str, offset: usize) -> Option<Block> {
-        let mut i = content.len();
+    // Numbers like 42, 1_000, 0xFF, 0b1010, 0o17, 1.5 and 1e-3, or the keywords true, false and null
+    fn get_literal(&self, content: &str, offset: usize) -> Result<Option<Block>, Error> {
+        if content.starts_with(|c: char| c.is_ascii_digit()) {
+            return self.get_number(content, offset).map(Some);
+        }
 
-        while i > 0 {
-            let slice = &content[..i];
-            if let Ok(i) = slice.parse::<i32>() {
-                return Some(Block::new(
-                    BlockType::Literal(Literal::Int(i)),
-                    Token::Literal,
-                    String::from(slice),
-                    offset
-                ));
-            } else if let Ok(f) = slice.parse::<f64>() {
-                return Some(Block::new(
-                    BlockType::Literal(Literal::Float(f)),
-                    Token::Literal,
-                    String::from(slice),
-                    offset
-                ));
-            } else if "null" == slice {
-                return Some(Block::new(
-                    BlockType::Literal(Literal::Null),
-                    Token::Literal,
-                    String::from(slice),
-                    offset
-                ));
+        let word = &content[..content.find(|c: char| !is_identifier_char(c)).unwrap_or(content.len())];
+        let literal = match word {
+            "true" => Literal::Bool(true),
+            "false" => Literal::Bool(false),
+            "null" => Literal::Null,
+            _ => return Ok(None)
+        };
+
+        Ok(Some(Block::new(
+            BlockType::Literal(literal),
+            Token::Literal,
+            String::from(word),
+            offset
+        )))
+    }
+
+    fn get_number(&self, content: &str, offset: usize) -> Result<Block, Error> {
+        let chars: Vec<char> = content.chars().collect();
+        let digits = |from: usize, radix: u32| from + chars[from..].iter()
+            .take_while(|c| c.is_digit(radix) || **c == '_')
+            .count();
+
+        let radix = match (chars[0], chars.get(1)) {
+            ('0', Some('x')) | ('0', Some('X')) => 16,
+            ('0', Some('b')) | ('0', Some('B')) => 2,
+            ('0', Some('o')) | ('0', Some('O')) => 8,
+            _ => 10
+        };
+
+        let mut end = if radix == 10 { digits(0, 10) } else { digits(2, radix) };
+        let mut is_float = false;
+
+        if radix == 10 {
+            // A fraction needs a digit after the dot, as 0..10 is a range and 1.len() a method call
+            if chars.get(end) == Some(&'.') && chars.get(end + 1).is_some_and(|c| c.is_ascii_digit()) {
+                end = digits(end + 1, 10);
+                is_float = true;
+            }
+
+            if let Some('e') | Some('E') = chars.get(end) {
+                let exponent = match chars.get(end + 1) {
+                    Some('-') | Some('+') => end + 2,
+                    _ => end + 1
+                };
+
+                if chars.get(exponent).is_some_and(|c| c.is_ascii_digit()) {
+                    end = digits(exponent, 10);
+                    is_float = true;
+                }
             }
-            i -= 1;
         }
 
-        None
+        // Letters or digits directly following a number, like in 0xZZ or 12ab, make it malformed
+        let malformed = chars.get(end).is_some_and(|c| is_identifier_char(*c)) || (radix != 10 && end == 2);
+        let width = end + chars[end..].iter().take_while(|c| is_identifier_char(**c)).count();
+        let slice: String = chars[..width].iter().collect();
+
+        let error = |help: String| Error::new(offset, width, ErrorType::LexerError(LexerErrorType::MalformedNumber))
+            .with_help(help);
+
+        if malformed {
+            return Err(error(format!("[{}] is not a valid number", slice)));
+        }
+
+        let cleaned: String = chars[if radix == 10 { 0 } else { 2 }..end].iter()
+            .filter(|c| **c != '_')
+            .collect();
+
+        let literal = if is_float {
+            match cleaned.parse::<f64>() {
+                Ok(f) => Literal::Float(f),
+                Err(_) => return Err(error(format!("[{}] is not a valid number", slice)))
+            }
+        } else {
+            match i64::from_str_radix(&cleaned, radix) {
+                Ok(i) => Literal::Int(i),
+                Err(_) => return Err(error(format!("[{}] does not fit in an Int", slice)))
+            }
+        };
+
+        Ok(Block::new(BlockType::Literal(literal), Token::Literal, slice, offset))
     }
 
     fn tokenize(&self, block: Block) -> LexerResult {
@@ -169,7 +358,7 @@ impl Lexer {
             if let Some(token) = self.get_token(slice, i + offset) {
                 i += token.width;
                 result.push_back(token);
-            } else if let Some(literal) = self.get_literal(slice, i + offset) {
+            } else if let Some(literal) = self.get_literal(slice, i + offset)? {
                 i += literal.width;
                 result.push_back(literal);
             } else if let Some(identifier) = self.get_identifier(slice, i + offset) {
@@ -185,70 +374,100 @@ impl Lexer {
     
         Ok(
             result.into_iter()
-                .filter(|v| {
-                    match v.block_type {
-                        BlockType::Token(Token::Space) |
-                        BlockType::Token(Token::Tab) |
-                        BlockType::Token(Token::NewLine) => false,
-                        _ => true
-                    }
-                })
+                .filter(|v| !matches!(
+                    v.block_type,
+                    BlockType::Token(Token::Space) |
+                    BlockType::Token(Token::Tab) |
+                    BlockType::Token(Token::NewLine)
+                ))
                 .collect()
         )
     }
 
+    // Removes line comments, and block comments which may be nested like /* /* */ */
     fn strip_comments(&self, block: Block) -> LexerResult {
+        let chars: Vec<char> = block.content.chars().collect();
         let mut result = LinkedList::<Block>::new();
         let mut buf: String = String::new();
     
         let mut positions: Vec<usize> = vec![];
     
-        let mut comment_count = 0;
         let mut is_comment = false;
+        let mut depth = 0; // Amount of open block comments
+        let mut opening = 0; // Position of the outermost open block comment
+        let mut skip = false; // The second character of a comment delimiter
     
-        for (i, v) in block.content.chars().enumerate() {
-            if v != '/' {
-                comment_count = 0;
+        for (i, &v) in chars.iter().enumerate() {
+            if skip {
+                skip = false;
+                continue;
             }
-    
-            match v {
-                '/' => {
-                    comment_count += 1;
-                    if !is_comment && comment_count >= 2 {
-                        buf.pop();
-    
-                        if buf.len() > 0 {
-                            result.push_back(Block::new(
-                                BlockType::Rest,
-                                Token::Rest,
-                                buf,
-                                block.offset + get_last(&positions)
-                            ));
+
+            let next = chars.get(i + 1).copied();
+
+            if depth > 0 {
+                match (v, next) {
+                    ('/', Some('*')) => {
+                        depth += 1;
+                        skip = true;
+                    },
+                    ('*', Some('/')) => {
+                        depth -= 1;
+                        skip = true;
+
+                        if depth == 0 {
+                            positions.push(i + 2);
                         }
+                    },
+                    _ => {}
+                }
+
+                continue;
+            }
+
+            if is_comment {
+                if v == '\n' {
+                    positions.push(i + 1);
+                    is_comment = false;
+                }
+
+                continue;
+            }
     
-                        positions.push(i + 1);
-                        buf = String::new();
-                        is_comment = true;
-    
-                        continue;
-                    }
-                },
-                '\n' => {
-                    if is_comment {
-                        positions.push(i + 1);
-                        buf = String::new();
-                        is_comment = false;
-    
-                        continue;
-                    }
-                },
-                _ => {}
+            if let ('/', Some('/')) | ('/', Some('*')) = (v, next) {
+                if !buf.is_empty() {
+                    result.push_back(Block::new(
+                        BlockType::Rest,
+                        Token::Rest,
+                        buf,
+                        block.offset + get_last(&positions)
+                    ));
+                }
+
+                buf = String::new();
+                skip = true;
+
+                if next == Some('/') {
+                    is_comment = true;
+                } else {
+                    depth = 1;
+                    opening = i;
+                }
+
+                continue;
             }
     
             buf.push(v);
         }
+
+        if depth > 0 {
+            return Err(
+                Error::new(block.offset + opening, 2, ErrorType::LexerError(LexerErrorType::UnterminatedComment))
+                    .with_help(String::from("this block comment is never closed"))
+            );
+        }
     
-        if buf.len() > 0 && !is_comment {
+        if !buf.is_empty() {
             result.push_back(Block::new(
                 BlockType::Rest,
                 Token::Rest,
@@ -259,35 +478,123 @@ impl Lexer {
     
         Ok(result)
     }
-    
+
+    // Splits a string literal starting at offset into text and lexed interpolations
+    fn string_literal(&self, content: &str, offset: usize) -> Result<Literal, Error> {
+        let chars: Vec<char> = content.chars().collect();
+        let mut parts = Vec::new();
+        let (mut start, mut i) = (0, 0);
+
+        let text = |from: usize, to: usize| -> Result<StringPart, Error> {
+            Ok(StringPart::Text {
+                literal: Literal::String(unescape(&chars[from..to].iter().collect::<String>(), offset + from)?),
+                offset: offset + from,
+                width: to - from
+            })
+        };
+
+        while i < chars.len() {
+            match chars[i] {
+                '\\' => i += 1,
+                '$' if chars.get(i + 1) == Some(&'{') => {
+                    let end = match interpolation_end(&chars, i + 2) {
+                        Some(end) => end,
+                        None => return Err(unclosed_interpolation(offset + i))
+                    };
+
+                    if start < i {
+                        parts.push(text(start, i)?);
+                    }
+
+                    // Running out of code is reported at the closing brace, rather than past it
+                    let mut blocks = self.lex(chars[i + 2..end].iter().collect(), offset + i + 2)?;
+                    if let Some(eof) = blocks.back_mut() {
+                        eof.offset = offset + end;
+                    }
+
+                    parts.push(StringPart::Code {
+                        blocks,
+                        offset: offset + i,
+                        width: end + 1 - i
+                    });
+
+                    i = end;
+                    start = end + 1;
+                },
+                _ => {}
+            }
+            i += 1;
+        }
+
+        if parts.is_empty() {
+            return Ok(Literal::String(unescape(content, offset)?));
+        }
+
+        if start < chars.len() {
+            parts.push(text(start, chars.len())?);
+        }
+
+        Ok(Literal::Interpolated(parts))
+    }
+
+    // Raw strings, like r"C:\path", are written with a leading r and keep their backslashes
     fn strip_strings(&self, block: Block) -> LexerResult {
+        let chars: Vec<char> = block.content.chars().collect();
+        let mut skip = 0; // Characters of an interpolation or comment delimiter left to be copied as they are
         let mut escaped = false;
         let mut is_string = false;
+        let mut is_raw = false;
         let mut comment_count = 0;
         let mut is_comment = false;
+        let mut comment_depth = 0; // Amount of open block comments, whose content is left to strip_comments
     
         let mut positions: Vec<usize> = vec![];
     
         let mut result = LinkedList::<Block>::new();
         let mut buf: String = String::new();
     
-        for (i, v) in block.content.chars().enumerate() {
+        for (i, &v) in chars.iter().enumerate() {
+            if skip > 0 {
+                skip -= 1;
+                buf.push(v);
+                continue;
+            }
+
             if is_comment && v != '\n' {
                 buf.push(v);
                 continue;
             }
+
+            if comment_depth > 0 {
+                match (v, chars.get(i + 1)) {
+                    ('/', Some('*')) => {
+                        comment_depth += 1;
+                        skip = 1;
+                    },
+                    ('*', Some('/')) => {
+                        comment_depth -= 1;
+                        skip = 1;
+                    },
+                    _ => {}
+                }
+
+                buf.push(v);
+                continue;
+            }
     
             if v != '/' {
                 comment_count = 0;
             }
     
             match v {
-                '/' => {
-                    if !is_string {
-                        comment_count += 1;
-                        if comment_count >= 2 {
-                            is_comment = true;
-                        }
+                '/' if !is_string => {
+                    comment_count += 1;
+                    if comment_count >= 2 {
+                        is_comment = true;
+                    } else if chars.get(i + 1) == Some(&'*') {
+                        comment_count = 0;
+                        comment_depth = 1;
+                        skip = 1;
                     }
                 },
                 '\n' => {
@@ -295,24 +602,42 @@ impl Lexer {
                     is_comment = false;
                 },
                 '\\' => {
-                    escaped = !escaped && is_string;
-                    if escaped { continue; }
-                },
-                '"' => {
-                    if !escaped {
-                        result.push_back(Block::new(
-                            if is_string { BlockType::Literal(Literal::String(buf.clone())) } else { BlockType::Rest },
-                            if is_string { Token::Literal } else { Token::Rest },
-                            buf,
-                            block.offset + get_last(&positions)
-                        ));
-    
-                        positions.push(i + 1);
-                        buf = String::new();
-                        is_string = !is_string;
+                    escaped = !escaped && is_string && !is_raw;
+                    if escaped {
+                        buf.push(v);
                         continue;
                     }
                 },
+                '$' if is_string && !escaped && !is_raw && chars.get(i + 1) == Some(&'{') => {
+                    skip = match interpolation_end(&chars, i + 2) {
+                        Some(end) => end - i,
+                        None => return Err(unclosed_interpolation(block.offset + i))
+                    };
+
+                    buf.push(v);
+                    continue;
+                },
+                '"' if !escaped => {
+                    let offset = block.offset + get_last(&positions);
+
+                    if is_string {
+                        // The block keeps the string as written, so that its width matches the source
+                        let value = if is_raw { Literal::String(buf.clone()) } else { self.string_literal(&buf, offset)? };
+                        result.push_back(Block::new(BlockType::Literal(value), Token::Literal, buf, offset));
+                    } else {
+                        is_raw = buf.ends_with('r') && !buf[..buf.len() - 1].ends_with(is_identifier_char);
+                        if is_raw {
+                            buf.pop();
+                        }
+
+                        result.push_back(Block::new(BlockType::Rest, Token::Rest, buf, offset));
+                    }
+    
+                    positions.push(i + 1);
+                    buf = String::new();
+                    is_string = !is_string;
+                    continue;
+                },
                 _ => {}
             }
     
@@ -329,7 +654,7 @@ impl Lexer {
             );
         }
     
-        if buf.len() >= 1 {
+        if !buf.is_empty() {
             // println!("{}, {}, {}", buf, block.offset, last_pos);
             result.push_back(Block::new(
                 BlockType::Rest,
@@ -342,8 +667,9 @@ impl Lexer {
         Ok(result)
     }
 
+    // Offset is where the query starts, such that interpolations can be lexed in place
     pub fn lex(&self, query: String, offset: usize) -> LexerResult {
-        let w_strings = self.strip_strings(Block::new(BlockType::Rest, Token::Rest, query.chars().collect(), 0))?;
+        let w_strings = self.strip_strings(Block::new(BlockType::Rest, Token::Rest, query.chars().collect(), offset))?;
         let w_comments = self.replace_rest(w_strings, &Self::strip_comments)?;
         let mut w_tokens = self.replace_rest(w_comments, &Self::tokenize)?;
 
@@ -351,7 +677,7 @@ impl Lexer {
             BlockType::Token(Token::SOF),
             Token::SOF,
             String::from(""),
-            0
+            offset
         ));
 
         let last = w_tokens.back().unwrap();
@@ -364,11 +690,6 @@ impl Lexer {
             l_offset + l_width + 1
         ));
 
-        if offset > 0 {
-            w_tokens.iter_mut()
-                .for_each(|mut v| v.offset += offset);
-        }
-    
         Ok(w_tokens)
     }
 }
\ No newline at end of file
@@ -91,4 +91,127 @@ fn comments_work() {
     //     offset: 50,
     //     width: 22, ..
     // });
-}
\ No newline at end of file
+}
+
+#[test]
+fn lexes_ranges() {
+    let lexer = Lexer::new();
+
+    let mut lexed = lexer.lex(String::from("0..10"), 0).unwrap().into_iter().skip(1);
+
+    assert_matches!(lexed.next().unwrap().block_type, BlockType::Literal(Literal::Int(0)));
+    assert_matches!(lexed.next().unwrap().block_type, BlockType::Token(Token::DoubleDot));
+    assert_matches!(lexed.next().unwrap().block_type, BlockType::Literal(Literal::Int(10)));
+}
+
+fn lex_string(code: &str) -> Result<String, Error> {
+    match Lexer::new().lex(String::from(code), 0)?.into_iter().nth(1).unwrap().block_type {
+        BlockType::Literal(Literal::String(s)) => Ok(s),
+        block_type => panic!("expected a string literal, got {:?}", block_type)
+    }
+}
+
+#[test]
+fn string_escapes() {
+    assert_eq!(lex_string(r#""a\nb\t\\\"""#).unwrap(), "a\nb\t\\\"");
+    assert_eq!(lex_string(r#""\u{48}\u{e5}\u{1F600}""#).unwrap(), "Hå\u{1F600}");
+    assert_eq!(lex_string(r#"r"C:\path\n""#).unwrap(), r"C:\path\n");
+    assert_eq!(lex_string("\"two\nlines\"").unwrap(), "two\nlines");
+}
+
+#[test]
+fn invalid_escapes() {
+    assert_matches!(
+        lex_string(r#"x = "ab\q";"#),
+        Err(Error { error_type: ErrorType::LexerError(LexerErrorType::InvalidEscapeSequence), offset: 7, width: 2, .. })
+    );
+    assert_matches!(
+        lex_string(r#""\u{110000}""#),
+        Err(Error { error_type: ErrorType::LexerError(LexerErrorType::InvalidEscapeSequence), offset: 1, width: 10, .. })
+    );
+    assert_matches!(
+        lex_string(r#""\u{4g}""#),
+        Err(Error { error_type: ErrorType::LexerError(LexerErrorType::InvalidEscapeSequence), offset: 1, width: 5, .. })
+    );
+}
+
+#[test]
+fn escaped_strings_keep_source_width() {
+    let mut lexed = Lexer::new().lex(String::from(r#""a\n" + 1"#), 0).unwrap().into_iter().skip(1);
+
+    assert_matches!(lexed.next().unwrap(), Block { offset: 1, width: 3, .. });
+    assert_matches!(lexed.next().unwrap(), Block { block_type: BlockType::Token(Token::Plus), offset: 6, .. });
+}
+
+#[test]
+fn block_comments() {
+    let lexer = Lexer::new();
+
+    let lexed = lexer.lex(String::from("1 /* a /* \"b */ c */ + /**/2 /* // */ * 3"), 0).unwrap();
+    let mut lexed = lexed.into_iter().skip(1);
+
+    assert_matches!(lexed.next().unwrap(), Block { block_type: BlockType::Literal(Literal::Int(1)), offset: 0, .. });
+    assert_matches!(lexed.next().unwrap(), Block { block_type: BlockType::Token(Token::Plus), offset: 21, .. });
+    assert_matches!(lexed.next().unwrap(), Block { block_type: BlockType::Literal(Literal::Int(2)), offset: 27, .. });
+    assert_matches!(lexed.next().unwrap(), Block { block_type: BlockType::Token(Token::Asterix), offset: 38, .. });
+    assert_matches!(lexed.next().unwrap(), Block { block_type: BlockType::Literal(Literal::Int(3)), offset: 40, .. });
+    assert_matches!(lexed.next().unwrap(), Block { block_type: BlockType::Token(Token::EOF), .. });
+}
+
+#[test]
+fn unterminated_block_comment() {
+    let lexer = Lexer::new();
+
+    assert_matches!(
+        lexer.lex(String::from("a = 1; /* x /* y */ \"z"), 0),
+        Err(Error { error_type: ErrorType::LexerError(LexerErrorType::UnterminatedComment), offset: 7, width: 2, .. })
+    );
+    assert_matches!(
+        lexer.lex(String::from("\"/*\" // /*"), 0),
+        Ok(_)
+    );
+}
+
+fn lex_literal(code: &str) -> Result<BlockType, Error> {
+    Ok(Lexer::new().lex(String::from(code), 0)?.into_iter().nth(1).unwrap().block_type)
+}
+
+#[test]
+fn numeric_literals() {
+    assert_matches!(lex_literal("0xFF"), Ok(BlockType::Literal(Literal::Int(255))));
+    assert_matches!(lex_literal("0b1010"), Ok(BlockType::Literal(Literal::Int(10))));
+    assert_matches!(lex_literal("0o17"), Ok(BlockType::Literal(Literal::Int(15))));
+    assert_matches!(lex_literal("1_000_000"), Ok(BlockType::Literal(Literal::Int(1000000))));
+    assert_matches!(lex_literal("0xFFFF_FFFF_FF"), Ok(BlockType::Literal(Literal::Int(1099511627775))));
+    assert_matches!(lex_literal("1e-3"), Ok(BlockType::Literal(Literal::Float(f))) if f == 0.001);
+    assert_matches!(lex_literal("2.5E2"), Ok(BlockType::Literal(Literal::Float(f))) if f == 250.0);
+    assert_matches!(lex_literal("1_0.0_1"), Ok(BlockType::Literal(Literal::Float(f))) if f == 10.01);
+}
+
+#[test]
+fn keyword_literals_are_whole_words() {
+    assert_matches!(lex_literal("nullx"), Ok(BlockType::Identifier(_)));
+    assert_matches!(lex_literal("inf"), Ok(BlockType::Identifier(_)));
+    assert_matches!(lex_literal("nan"), Ok(BlockType::Identifier(_)));
+    assert_matches!(lex_literal("null"), Ok(BlockType::Literal(Literal::Null)));
+}
+
+#[test]
+fn malformed_numbers() {
+    assert_matches!(
+        lex_literal("x = 0xZZ;"),
+        Err(Error { error_type: ErrorType::LexerError(LexerErrorType::MalformedNumber), offset: 4, width: 4, .. })
+    );
+    assert_matches!(
+        lex_literal("12ab + 1"),
+        Err(Error { error_type: ErrorType::LexerError(LexerErrorType::MalformedNumber), offset: 0, width: 4, .. })
+    );
+    assert_matches!(
+        lex_literal("1e"),
+        Err(Error { error_type: ErrorType::LexerError(LexerErrorType::MalformedNumber), .. })
+    );
+    assert_matches!(
+        lex_literal("9223372036854775808"),
+        Err(Error { error_type: ErrorType::LexerError(LexerErrorType::MalformedNumber), offset: 0, width: 19, .. })
+    );
+}
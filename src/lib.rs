@@ -1,3 +1,6 @@
+// Every stage returns the same Error, which carries the code and file it is displayed with
+#![allow(clippy::result_large_err)]
+
 #[cfg(test)] #[macro_use] extern crate matches;
 #[macro_use] extern crate maplit;
 #[macro_use] extern crate lazy_static;
@@ -5,6 +8,7 @@
 pub mod error;
 pub mod lexer;
 pub mod parser;
+pub mod resolver;
 pub mod compiler;
 pub mod vm;
 
@@ -1,3 +1,5 @@
+#![allow(clippy::result_large_err)]
+
 use std::io::prelude::*;
 use std::fs::File;
 use ::std::io::{Write};
@@ -23,15 +25,17 @@ fn flush() {
 
 struct Lang {
     vm: vm::VM,
+    resolver: resolver::Resolver,
     compiled: Program,
     mode: Mode,
     code_offset: usize
 }
 
-impl<'a> Lang {
+impl Lang {
     pub fn new() -> Self {
         Lang {
             vm: vm::VM::new(),
+            resolver: resolver::Resolver::new(),
             compiled: Vec::new(),
             mode: Mode::Run,
             code_offset: 0
@@ -47,6 +51,14 @@ impl<'a> Lang {
         self.mode = mode;
     }
 
+    // In strict mode, assigning to a name that was not declared with let or const is an error
+    pub fn set_strict(&mut self, strict: bool) {
+        println!("Strict mode [{}]", if strict { "on" } else { "off" });
+        flush();
+
+        self.resolver.set_strict(strict);
+    }
+
     pub fn run(&mut self, code: &str) -> Result<String, Error> {
         let lexer = lexer::Lexer::new();
         let mut parser = parser::Parser::new();
@@ -60,6 +72,7 @@ impl<'a> Lang {
 
                 let lexed = lexer.lex(code.clone(), self.code_offset)?;
                 let parsed = parser.parse(&lexed)?;
+                self.resolver.resolve(&parsed)?;
                 let mut compiled = compiler.compile(&parsed)?;
 
                 self.code_offset += code.len();
@@ -67,10 +80,12 @@ impl<'a> Lang {
                 self.compiled.append(&mut compiled);
 
                 let executed = self.vm.exec(&self.compiled, offset)?;
-                Ok(format!("{}", executed))
+                self.resolver.commit();
+
+                Ok(executed.to_string())
             },
             Mode::Lexed => {
-                let lexed_res = lexer.lex(String::from(code.clone()), 0)
+                let lexed_res = lexer.lex(code.clone(), 0)
                     .map(|v| v.into_iter().map(|v| v.block_type).collect::<Vec<BlockType>>());
                 Ok(format!("{:#?}", lexed_res))
             },
@@ -109,6 +124,8 @@ fn shell() {
             "$compiled\n" => lang.set_mode(Mode::Compiled),
             "$parsed\n" => lang.set_mode(Mode::Parsed),
             "$lexed\n" => lang.set_mode(Mode::Lexed),
+            "$strict\n" => lang.set_strict(true),
+            "$loose\n" => lang.set_strict(false),
             "$gc\n" => lang.vm.garbage(),
             _ => match lang.run(buf.as_ref()) {
                 Ok(res) => println!("{}", res),
@@ -122,8 +139,9 @@ fn shell() {
     }
 }
 
-fn file(file_name: &str) {
+fn file(file_name: &str, strict: bool) {
     let mut lang = Lang::new();
+    lang.resolver.set_strict(strict);
 
     let mut file = File::open(file_name).expect("Unable to open the file");
     let mut contents = String::new();
@@ -134,7 +152,7 @@ fn file(file_name: &str) {
     match lang.run(&contents) {
         Ok(res) => println!("{}", res),
         Err(err) => println!("{}", err
-            .with_code(String::from(contents))
+            .with_code(contents)
             .with_file(String::from(file_name))
             // .with_file(String::from("src/main.lang"))
         )
@@ -146,7 +164,8 @@ fn main() {
 
     match args.len() {
         1 => shell(),
-        2 => file(&args[1]),
+        2 => file(&args[1], false),
+        3 if args[1] == "--strict" => file(&args[2], true),
         _ => println!("Wrong number of command line arguments")
     }
     
@@ -8,15 +8,20 @@ type ExpressionResult<'a> = Result<Expression<'a>, Error>;
 
 #[derive(Debug)]
 pub struct Declaration<'a> {
-    offset: usize,
-    width: usize,
-    content: &'a str,
+    pub offset: usize,
+    pub width: usize,
+    pub content: &'a str,
     pub declaration_type: DeclarationType<'a>,
 }
 
 #[derive(Debug)]
 pub enum DeclarationType<'a> {
-    Statement(Statement<'a>)
+    Statement(Statement<'a>),
+    Variable { // let or const, scoped to the enclosing block
+        constant: bool,
+        pattern: Pattern<'a>,
+        value: Option<Expression<'a>> // Always given for constants and list patterns
+    }
 }
 
 #[derive(Debug)]
@@ -30,7 +35,19 @@ pub struct Statement<'a> {
 
 #[derive(Debug)]
 pub enum StatementType<'a> {
-    Expression(Expression<'a>)
+    Expression(Expression<'a>),
+    While {
+        condition: Expression<'a>,
+        body: AST<'a>
+    },
+    For {
+        identifier: &'a str,
+        iterable: Expression<'a>,
+        body: AST<'a>
+    },
+    Break,
+    Continue,
+    Return(Option<Expression<'a>>)
 }
 
 #[derive(Debug)]
@@ -46,10 +63,26 @@ pub enum ExpressionType<'a> {
     Empty,
     Primary(Primary<'a>),
     List(Vec<Box<Expression<'a>>>),
+    Interpolation(Vec<Expression<'a>>), // String literals and blocks, which are concatenated
+    Map(Vec<(&'a str, Box<Expression<'a>>)>),
     ListIndex {
         list: Box<Expression<'a>>,
         index: Box<Expression<'a>>
     },
+    Slice {
+        list: Box<Expression<'a>>,
+        start: Option<Box<Expression<'a>>>, // From the first item if left out
+        end: Option<Box<Expression<'a>>> // To the last item if left out
+    },
+    Member {
+        object: Box<Expression<'a>>,
+        name: &'a str
+    },
+    Spread(Box<Expression<'a>>), // Items of a list spread into a list literal or call arguments, or the rest of a list pattern
+    Destructure {
+        pattern: Pattern<'a>,
+        value: Box<Expression<'a>>
+    },
     Binary {
         left: Box<Expression<'a>>,
         right: Box<Expression<'a>>,
@@ -57,13 +90,45 @@ pub enum ExpressionType<'a> {
         offset: usize, // operator offset
         width: usize // operator width
     },
+    Unary {
+        expr: Box<Expression<'a>>,
+        operator: Token,
+        offset: usize, // operator offset
+        width: usize // operator width
+    },
     Function {
-        pars: Vec<&'a str>,
+        pars: Vec<Parameter<'a>>,
+        rest: Option<&'a str>, // Bound to a list of the remaining arguments, as in (a, ...args) => ...
         body: AST<'a>
     },
     FunctionCall {
         func: Box<Expression<'a>>,
-        args: Vec<Box<Expression<'a>>>
+        args: Vec<Box<Expression<'a>>>,
+        named: Vec<(&'a str, Box<Expression<'a>>)> // Named arguments, like f(b: 3), always after the positional ones
+    },
+    Block(AST<'a>),
+    If {
+        condition: Box<Expression<'a>>,
+        body: Box<Expression<'a>>,
+        else_body: Option<Box<Expression<'a>>> // Either a Block or another If
+    }
+}
+
+#[derive(Debug)]
+pub struct Parameter<'a> {
+    pub pattern: Pattern<'a>,
+    pub default: Option<Expression<'a>> // Evaluated when the argument is left out
+}
+
+// What a value is bound to, either a name or the items of a list like [a, [b, c], ...rest]
+#[derive(Debug)]
+pub enum Pattern<'a> {
+    Identifier(&'a str),
+    List {
+        items: Vec<Pattern<'a>>,
+        rest: Option<&'a str>, // Bound to a list of the remaining items
+        offset: usize,
+        width: usize
     }
 }
 
@@ -78,6 +143,12 @@ pub struct Parser<'a> {
     lexed: Vec<&'a Block>
 }
 
+impl<'a> Default for Parser<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<'a> Parser<'a> {
     pub fn new() -> Self {
         Parser {
@@ -87,8 +158,7 @@ impl<'a> Parser<'a> {
     }
 
     fn get_at(&self, index: usize) -> Option<&'a Block> {
-        self.lexed.get(index)
-            .map(|v| *v)
+        self.lexed.get(index).copied()
     }
  
     fn peek(&self) -> Option<&'a Block> {
@@ -128,7 +198,7 @@ impl<'a> Parser<'a> {
             }
         }
 
-        return None;
+        None
     }
 
     fn binary(expr: Expression<'a>, right: Expression<'a>, block: &'a Block) -> Expression<'a> {
@@ -148,50 +218,56 @@ impl<'a> Parser<'a> {
 
     fn match_lambda(&mut self) -> Result<Option<Expression<'a>>, Error> {
         let start;
-        let mut pars: Vec<&'a str> = vec![];
+        let mut pars: Vec<Parameter<'a>> = vec![];
+        let mut rest = None;
 
         if let Some(parenthesis) = self.get(&[Token::ParOpen]) {
             start = parenthesis.offset;
-            while let Some(arg) = self.get(&[Token::Identifier]) {
-                pars.push(&arg.content);
-                if let None = self.get(&[Token::Comma]) {
+            while let Some(pattern) = self.pattern() {
+                // Not being able to parse a default means that this is not a lambda, like in (a = ])
+                let default = match self.get(&[Token::Equals]) {
+                    Some(_) => match self.function() {
+                        Ok(default) => Some(default),
+                        Err(_) => return Ok(None)
+                    },
+                    None => None
+                };
+
+                pars.push(Parameter { pattern, default });
+                if self.get(&[Token::Comma]).is_none() {
                     break;
                 }
             }
-            if let None = self.get(&[Token::ParClosed]) {
+
+            // The rest parameter is always last
+            if self.get(&[Token::TripleDot]).is_some() {
+                match self.get(&[Token::Identifier]) {
+                    Some(identifier) => rest = Some(&*identifier.content),
+                    None => return Ok(None)
+                }
+            }
+
+            if self.get(&[Token::ParClosed]).is_none() {
                 return Ok(None);
             }
         } else if let Some(arg) = self.get(&[Token::Identifier]) {
             start = arg.offset;
-            pars.push(&arg.content);
+            pars.push(Parameter { pattern: Pattern::Identifier(&arg.content), default: None });
         } else {
             return Ok(None);
         }
 
-        if let None = self.get(&[Token::Lambda]) {
+        if self.get(&[Token::Lambda]).is_none() {
             return Ok(None);
         }
 
-        let end: usize;
-        let body = if let Some(open_bracket) = self.get(&[Token::BracketOpen]) {
-            let mut declarations = vec![];
-
-            loop {
-                if self.is_end() {
-                    // return Ok(None);
-                    return Err(Error::new(
-                        open_bracket.offset,
-                        open_bracket.width,
-                        ErrorType::ParserError(ParserErrorType::UnclosedBracket)
-                    ))
-                } else if let Some(close_bracket) = self.get(&[Token::BracketClosed]) {
-                    end = close_bracket.offset + close_bracket.width;
-                    break;
-                }
-
-                declarations.push(self.declaration()?);
-            }
+        // A map literal is an expression body, rather than a block
+        let open_bracket = if self.is_map() { None } else { self.get(&[Token::BracketOpen]) };
 
+        let end: usize;
+        let body = if let Some(open_bracket) = open_bracket {
+            let (declarations, close) = self.block_body(open_bracket)?;
+            end = close;
             declarations
         } else {
             let expr = self.expression()?;
@@ -221,11 +297,123 @@ impl<'a> Parser<'a> {
             content: "",
             expression_type: ExpressionType::Function {
                 pars,
+                rest,
                 body
             }
         }))
     }
 
+    // A parameter pattern, or None if the upcoming tokens are not one
+    fn pattern(&mut self) -> Option<Pattern<'a>> {
+        if let Some(identifier) = self.get(&[Token::Identifier]) {
+            return Some(Pattern::Identifier(&identifier.content));
+        }
+
+        let open = self.get(&[Token::BraceOpen])?;
+        let mut items = vec![];
+        let mut rest = None;
+
+        loop {
+            if let Some(close) = self.get(&[Token::BraceClosed]) {
+                return Some(Pattern::List {
+                    items,
+                    rest,
+                    offset: open.offset,
+                    width: close.offset + close.width - open.offset
+                });
+            }
+
+            // The rest must be last
+            if rest.is_some() {
+                return None;
+            }
+
+            if self.get(&[Token::TripleDot]).is_some() {
+                rest = Some(&*self.get(&[Token::Identifier])?.content);
+            } else {
+                items.push(self.pattern()?);
+            }
+
+            if let (None, None) = (self.get(&[Token::Comma]), self.check(Token::BraceClosed)) {
+                return None;
+            }
+        }
+    }
+
+    // Turns an assignment target like [a, ...rest] into a pattern
+    fn to_pattern(expr: Expression<'a>) -> Result<Pattern<'a>, Error> {
+        let invalid = |expr: &Expression| Error::new(expr.offset, expr.width, ErrorType::ParserError(ParserErrorType::InvalidPattern))
+            .with_help(String::from("only names, lists of patterns and a final ...rest can be assigned to"));
+
+        match expr.expression_type {
+            ExpressionType::Primary(Primary::Identifier(identifier)) => Ok(Pattern::Identifier(identifier)),
+            ExpressionType::List(values) => {
+                let (mut items, mut rest) = (vec![], None);
+                let count = values.len();
+
+                for (i, value) in values.into_iter().enumerate() {
+                    match value.expression_type {
+                        ExpressionType::Spread(spread) => match spread.expression_type {
+                            ExpressionType::Primary(Primary::Identifier(identifier)) if i + 1 == count => rest = Some(identifier),
+                            _ => return Err(invalid(&spread))
+                        },
+                        _ => items.push(Parser::to_pattern(*value)?)
+                    }
+                }
+
+                Ok(Pattern::List { items, rest, offset: expr.offset, width: expr.width })
+            },
+            _ => Err(invalid(&expr))
+        }
+    }
+
+    // Parses declarations up until the closing bracket, returns them together with the end offset
+    fn block_body(&mut self, open_bracket: &'a Block) -> Result<(AST<'a>, usize), Error> {
+        let mut declarations = vec![];
+
+        loop {
+            if self.is_end() {
+                return Err(Error::new(
+                    open_bracket.offset,
+                    open_bracket.width,
+                    ErrorType::ParserError(ParserErrorType::UnclosedBracket)
+                ))
+            } else if let Some(close_bracket) = self.get(&[Token::BracketClosed]) {
+                return Ok((declarations, close_bracket.offset + close_bracket.width));
+            }
+
+            declarations.push(self.declaration()?);
+        }
+    }
+
+    fn block(&mut self) -> ExpressionResult<'a> {
+        let (body, start, end) = self.block_ast()?;
+
+        Ok(Expression {
+            offset: start,
+            width: end - start,
+            content: "",
+            expression_type: ExpressionType::Block(body)
+        })
+    }
+
+    // Parses a block, returns its declarations together with the start and end offsets
+    fn block_ast(&mut self) -> Result<(AST<'a>, usize, usize), Error> {
+        if let Some(open_bracket) = self.get(&[Token::BracketOpen]) {
+            let (body, end) = self.block_body(open_bracket)?;
+            return Ok((body, open_bracket.offset, end));
+        }
+
+        let (offset, width) = self.peek()
+            .map(|v| (v.offset, v.width))
+            .unwrap_or((0, 0));
+
+        Err(
+            Error::new(offset, width, ErrorType::ParserError(ParserErrorType::ExpectedBlock))
+                .with_help(String::from("expected a block starting with '{'"))
+        )
+    }
+
     fn ast(&mut self) -> Result<AST<'a>, Error> {
         let mut ast = vec![];
 
@@ -233,10 +421,14 @@ impl<'a> Parser<'a> {
             ast.push(self.declaration()?);
         }
 
-        return Ok(ast);
+        Ok(ast)
     }
 
     fn declaration(&mut self) -> Result<Declaration<'a>, Error> {
+        if let Some(keyword) = self.get(&[Token::Let, Token::Const]) {
+            return self.variable(keyword);
+        }
+
         let stmt = self.statement()?;
 
         Ok(Declaration {
@@ -247,7 +439,124 @@ impl<'a> Parser<'a> {
         })
     }
 
+    fn variable(&mut self, keyword: &'a Block) -> Result<Declaration<'a>, Error> {
+        let constant = keyword.token == Token::Const;
+
+        let pattern = match self.pattern() {
+            Some(pattern) => pattern,
+            None => return Err(self.unexpected().with_help(String::from("expected a name or a list pattern to declare")))
+        };
+
+        let value = match self.get(&[Token::Equals]) {
+            Some(_) => Some(self.expression()?),
+            None if constant => return Err(self.unexpected().with_help(String::from("constants must be given a value"))),
+            None if matches!(pattern, Pattern::List { .. }) => return Err(
+                self.unexpected().with_help(String::from("a list pattern must be given a value to destructure"))
+            ),
+            None => None
+        };
+
+        // The pattern has no offsets of its own, so the declaration ends at the last block that was taken
+        let end = self.get_at(self.index)
+            .map(|v| v.offset + v.width)
+            .unwrap_or(keyword.offset + keyword.width);
+        self.get(&[Token::SemiColon]);
+
+        Ok(Declaration {
+            offset: keyword.offset,
+            width: end - keyword.offset,
+            content: &keyword.content,
+            declaration_type: DeclarationType::Variable { constant, pattern, value }
+        })
+    }
+
     fn statement(&mut self) -> Result<Statement<'a>, Error> {
+        if let Some(while_block) = self.get(&[Token::While]) {
+            let condition = self.expression()?;
+            let (body, _, end) = self.block_ast()?;
+
+            return Ok(Statement {
+                offset: while_block.offset,
+                width: end - while_block.offset,
+                content: &while_block.content,
+                end: self.get(&[Token::SemiColon]).is_some(),
+                statement_type: StatementType::While { condition, body }
+            });
+        }
+
+        if let Some(for_block) = self.get(&[Token::For]) {
+            let identifier = match self.get(&[Token::Identifier]) {
+                Some(identifier) => identifier,
+                None => return Err(self.unexpected())
+            };
+
+            if self.get(&[Token::In]).is_none() {
+                return Err(self.unexpected());
+            }
+
+            let iterable = self.expression()?;
+            let (body, _, end) = self.block_ast()?;
+
+            return Ok(Statement {
+                offset: for_block.offset,
+                width: end - for_block.offset,
+                content: &for_block.content,
+                end: self.get(&[Token::SemiColon]).is_some(),
+                statement_type: StatementType::For {
+                    identifier: &identifier.content,
+                    iterable,
+                    body
+                }
+            });
+        }
+
+        if let Some(return_block) = self.get(&[Token::Return]) {
+            // A bare return is ended by a semicolon, the end of the block or the end of the file
+            let expr = if self.check(Token::SemiColon).is_some() || self.check(Token::BracketClosed).is_some() || self.is_end() {
+                None
+            } else {
+                Some(self.expression()?)
+            };
+
+            let end = expr.as_ref()
+                .map(|v| v.offset + v.width)
+                .unwrap_or(return_block.offset + return_block.width);
+
+            return Ok(Statement {
+                offset: return_block.offset,
+                width: end - return_block.offset,
+                content: &return_block.content,
+                end: self.get(&[Token::SemiColon]).is_some(),
+                statement_type: StatementType::Return(expr)
+            });
+        }
+
+        // A bracket opening a statement is a block, unless it is a map literal like { key: ... } or {}
+        if self.check(Token::BracketOpen).is_some() && !self.is_map() && self.get_at(self.index + 2).is_some_and(|v| v.token != Token::BracketClosed) {
+            let block = self.block()?;
+
+            return Ok(Statement {
+                offset: block.offset,
+                width: block.width,
+                content: block.content,
+                end: self.get(&[Token::SemiColon]).is_some(),
+                statement_type: StatementType::Expression(block)
+            });
+        }
+
+        if let Some(block) = self.get(&[Token::Break, Token::Continue]) {
+            return Ok(Statement {
+                offset: block.offset,
+                width: block.width,
+                content: &block.content,
+                end: self.get(&[Token::SemiColon]).is_some(),
+                statement_type: match block.token {
+                    Token::Break => StatementType::Break,
+                    _ => StatementType::Continue
+                }
+            });
+        }
+
         let expr = self.expression()?;
 
         Ok(Statement {
@@ -264,63 +573,97 @@ impl<'a> Parser<'a> {
     }
 
     fn assign(&mut self) -> ExpressionResult<'a> {
-        let mut expr = self.list()?;
+        let mut expr = self.function()?;
+
+        while let Some(block) = self.get(&[
+            Token::Equals,
+            Token::PlusEquals,
+            Token::MinusEquals,
+            Token::AsterixEquals,
+            Token::FSlashEquals,
+            Token::PercentEquals,
+            Token::DoubleAsterixEquals
+        ]) {
+            expr = match (&expr.expression_type, block.token) {
+                (ExpressionType::List(_), Token::Equals) => {
+                    let value = self.function()?;
+
+                    Expression {
+                        offset: expr.offset,
+                        width: value.offset + value.width - expr.offset,
+                        content: "",
+                        expression_type: ExpressionType::Destructure {
+                            pattern: Parser::to_pattern(expr)?,
+                            value: Box::new(value)
+                        }
+                    }
+                },
+                _ => Parser::binary(expr, self.function()?, block)
+            };
+        }
 
-        while let Some(block) = self.get(&[Token::Equals]) {
-            expr = Parser::binary(expr, self.list()?, block);
+        Ok(expr)
+    }
+
+    fn function(&mut self) -> ExpressionResult<'a> {
+        let reverse = self.index;
+
+        if let Some(function) = self.match_lambda()? {
+            Ok(function)
+        } else {
+            self.reverse(reverse);
+            self.or()
+        }
+    }
+
+    fn or(&mut self) -> ExpressionResult<'a> {
+        let mut expr = self.and()?;
+
+        while let Some(block) = self.get(&[Token::DoublePipe]) {
+            expr = Parser::binary(expr, self.and()?, block);
         }
 
         Ok(expr)
     }
 
-    fn list(&mut self) -> ExpressionResult<'a> {
-        if let Some(open) = self.get(&[Token::BraceOpen]) {
-            let mut values = Vec::new();
-            let closed;
+    fn and(&mut self) -> ExpressionResult<'a> {
+        let mut expr = self.equality()?;
 
-            loop {
-                if self.is_end() {
-                    return Err(Error::new(open.offset, open.width, ErrorType::ParserError(ParserErrorType::UnclosedBrace)));
-                }
+        while let Some(block) = self.get(&[Token::DoubleAmpersand]) {
+            expr = Parser::binary(expr, self.equality()?, block);
+        }
 
-                if let Some(brace) = self.get(&[Token::BraceClosed]) {
-                    closed = brace;
-                    break;
-                }
+        Ok(expr)
+    }
 
-                if let Some(comma) = self.get(&[Token::Comma]) {
-                    values.push(Box::new(Expression {
-                        offset: comma.offset,
-                        width: comma.width,
-                        content: &comma.content,
-                        expression_type: ExpressionType::Primary(Primary::Literal(&Literal::Null))
-                    }));
-                } else {
-                    values.push(Box::new(self.expression()?));
-                    self.get(&[Token::Comma]);
-                }
-            }
+    fn equality(&mut self) -> ExpressionResult<'a> {
+        let mut expr = self.comparison()?;
 
-            return Ok(Expression {
-                offset: open.offset,
-                width: closed.offset + closed.width,
-                content: "",
-                expression_type: ExpressionType::List(values)
-            });
+        while let Some(block) = self.get(&[Token::DoubleEquals, Token::NotEquals]) {
+            expr = Parser::binary(expr, self.comparison()?, block);
         }
 
-        self.function()
+        Ok(expr)
     }
 
-    fn function(&mut self) -> ExpressionResult<'a> {
-        let reverse = self.index;
+    fn comparison(&mut self) -> ExpressionResult<'a> {
+        let mut expr = self.range()?;
 
-        if let Some(function) = self.match_lambda()? {
-            return Ok(function);
-        } else {
-            self.reverse(reverse);
-            return self.addition();
+        while let Some(block) = self.get(&[Token::Less, Token::LessEquals, Token::Greater, Token::GreaterEquals]) {
+            expr = Parser::binary(expr, self.range()?, block);
+        }
+
+        Ok(expr)
+    }
+
+    fn range(&mut self) -> ExpressionResult<'a> {
+        let expr = self.addition()?;
+
+        if let Some(block) = self.get(&[Token::DoubleDot]) {
+            return Ok(Parser::binary(expr, self.addition()?, block));
         }
+
+        Ok(expr)
     }
 
     fn addition(&mut self) -> ExpressionResult<'a> {
@@ -334,111 +677,375 @@ impl<'a> Parser<'a> {
     }
 
     fn multiplication(&mut self) -> ExpressionResult<'a> {
-        let mut expr = self.list_index()?;
+        let mut expr = self.unary()?;
 
-        while let Some(block) = self.get(&[Token::Asterix, Token::FSlash]) {
-            expr = Parser::binary(expr, self.list_index()?, block);
+        while let Some(block) = self.get(&[Token::Asterix, Token::FSlash, Token::Percent]) {
+            expr = Parser::binary(expr, self.unary()?, block);
         }
 
         Ok(expr)
     }
 
-    fn list_index(&mut self) -> ExpressionResult<'a> {
-        let mut expr = self.function_call()?;
+    fn unary(&mut self) -> ExpressionResult<'a> {
+        if let Some(block) = self.get(&[Token::Exclamation, Token::Minus]) {
+            let expr = self.unary()?;
 
-        while let Some(open) = self.get(&[Token::BraceOpen]) {
-            let index = self.expression()?;
+            return Ok(Expression {
+                offset: block.offset,
+                width: expr.offset + expr.width - block.offset,
+                content: &block.content,
+                expression_type: ExpressionType::Unary {
+                    expr: Box::new(expr),
+                    operator: block.token,
+                    offset: block.offset,
+                    width: block.width
+                }
+            });
+        }
 
-            if let Some(close) = self.get(&[Token::BraceClosed]) {
-                expr = Expression {
-                    offset: expr.offset,
-                    width: expr.width + close.offset + close.width - open.offset,
-                    content: "",
-                    expression_type: ExpressionType::ListIndex {
-                        list: Box::from(expr),
-                        index: Box::from(index)
-                    }
-                };
-            } else {
-                return Err(Error::new(open.offset, open.width, ErrorType::ParserError(ParserErrorType::UnclosedBrace)));
-            }
+        self.power()
+    }
+
+    fn power(&mut self) -> ExpressionResult<'a> {
+        let expr = self.postfix()?;
+
+        // Right associative, and the exponent may be negated, as in 2 ** -1
+        if let Some(block) = self.get(&[Token::DoubleAsterix]) {
+            return Ok(Parser::binary(expr, self.unary()?, block));
         }
 
         Ok(expr)
     }
 
-    fn function_call(&mut self) -> ExpressionResult<'a> {
+    // Indexing, function calls and member accesses, which may be chained like a.b(c)[d]
+    fn postfix(&mut self) -> ExpressionResult<'a> {
         let mut expr = self.primary()?;
 
-        while let Some(open) = self.get(&[Token::ParOpen]) {
-            let mut args = Vec::new();
+        loop {
+            expr = if let Some(open) = self.get(&[Token::BraceOpen]) {
+                self.list_index(expr, open)?
+            } else if let Some(open) = self.get(&[Token::ParOpen]) {
+                self.function_call(expr, open)?
+            } else if let Some(dot) = self.get(&[Token::Dot]) {
+                self.member(expr, dot)?
+            } else {
+                return Ok(expr);
+            };
+        }
+    }
+
+    // Either an index like a[i], or a slice like a[i:j] where both bounds may be left out
+    fn list_index(&mut self, expr: Expression<'a>, open: &'a Block) -> ExpressionResult<'a> {
+        let index = match self.check(Token::Colon) {
+            Some(_) => None,
+            None => Some(self.expression()?)
+        };
+
+        let slice = match self.get(&[Token::Colon]) {
+            Some(_) => Some(match self.check(Token::BraceClosed) {
+                Some(_) => None,
+                None => Some(Box::from(self.expression()?))
+            }),
+            None => None
+        };
+
+        let close = match self.get(&[Token::BraceClosed]) {
+            Some(close) => close,
+            None => return Err(Error::new(open.offset, open.width, ErrorType::ParserError(ParserErrorType::UnclosedBrace)))
+        };
+
+        Ok(Expression {
+            offset: expr.offset,
+            width: close.offset + close.width - expr.offset,
+            content: "",
+            expression_type: match (index, slice) {
+                (index, Some(end)) => ExpressionType::Slice {
+                    list: Box::from(expr),
+                    start: index.map(Box::from),
+                    end
+                },
+                (Some(index), None) => ExpressionType::ListIndex {
+                    list: Box::from(expr),
+                    index: Box::from(index)
+                },
+                (None, None) => return Err(self.unexpected())
+            }
+        })
+    }
+
+    fn function_call(&mut self, expr: Expression<'a>, open: &'a Block) -> ExpressionResult<'a> {
+        let mut args = Vec::new();
+        let mut named = Vec::new();
+        let closed;
+
+        loop {
+            if self.is_end() {
+                return Err(Error::new(open.offset, open.width, ErrorType::ParserError(ParserErrorType::UnclosedParenthesis)));
+            }
+
+            if let Some(par) = self.get(&[Token::ParClosed]) {
+                closed = par;
+                break;
+            }
+
+            let is_named = self.check(Token::Identifier).is_some()
+                && self.get_at(self.index + 2).is_some_and(|v| v.token == Token::Colon);
+
+            if is_named {
+                let name = self.get(&[Token::Identifier]).unwrap();
+                self.get(&[Token::Colon]);
+
+                named.push((&*name.content, Box::new(self.expression()?)));
+                self.get(&[Token::Comma]);
+            } else if !named.is_empty() {
+                return Err(self.unexpected().with_help(String::from("positional arguments must come before named ones")));
+            } else if let Some(dots) = self.get(&[Token::TripleDot]) {
+                args.push(Box::new(self.spread(dots)?));
+                self.get(&[Token::Comma]);
+            } else if let Some(comma) = self.get(&[Token::Comma]) {
+                args.push(Box::new(Expression {
+                    offset: comma.offset,
+                    width: comma.width,
+                    content: &comma.content,
+                    expression_type: ExpressionType::Primary(Primary::Literal(&Literal::Null))
+                }));
+            } else {
+                args.push(Box::new(self.expression()?));
+                self.get(&[Token::Comma]);
+            }
+        }
+
+        // Remove arguments in the case of empty arguments function call, like a()
+        if args.len() == 1 {
+            if let ExpressionType::Primary(Primary::Literal(&Literal::Null)) = args[0].expression_type {
+                args.pop();
+            }
+        }
+
+        Ok(Expression {
+            offset: expr.offset,
+            width: closed.offset - expr.offset + 1,
+            content: expr.content,
+            expression_type: ExpressionType::FunctionCall {
+                func: Box::new(expr),
+                args,
+                named
+            }
+        })
+    }
+
+    fn member(&mut self, expr: Expression<'a>, dot: &'a Block) -> ExpressionResult<'a> {
+        let name = match self.get(&[Token::Identifier]) {
+            Some(name) => name,
+            None => return Err(self.unexpected().with_help(String::from("expected a member name after '.'")))
+        };
+
+        Ok(Expression {
+            offset: expr.offset,
+            width: name.offset + name.width - expr.offset,
+            content: &dot.content,
+            expression_type: ExpressionType::Member {
+                object: Box::new(expr),
+                name: &name.content
+            }
+        })
+    }
+
+    fn conditional(&mut self, if_block: &'a Block) -> ExpressionResult<'a> {
+        let condition = self.expression()?;
+        let body = self.block()?;
+
+        let else_body = if self.get(&[Token::Else]).is_some() {
+            Some(Box::new(match self.get(&[Token::If]) {
+                Some(else_if) => self.conditional(else_if)?,
+                None => self.block()?
+            }))
+        } else {
+            None
+        };
+
+        let end = else_body.as_ref()
+            .map(|v| v.offset + v.width)
+            .unwrap_or(body.offset + body.width);
+
+        Ok(Expression {
+            offset: if_block.offset,
+            width: end - if_block.offset,
+            content: "",
+            expression_type: ExpressionType::If {
+                condition: Box::new(condition),
+                body: Box::new(body),
+                else_body
+            }
+        })
+    }
+
+    fn primary(&mut self) -> ExpressionResult<'a> {
+        if let Some(if_block) = self.get(&[Token::If]) {
+            return self.conditional(if_block);
+        }
+
+        if let Some(block) = self.get(&[Token::Literal, Token::Identifier]) {
+            if let BlockType::Literal(Literal::Interpolated(parts)) = &block.block_type {
+                return self.interpolation(block, parts);
+            }
+
+            return Ok(Expression {
+                offset: block.offset,
+                width: block.width,
+                content: &block.content,
+                expression_type: match block.block_type {
+                    BlockType::Literal(ref literal) => ExpressionType::Primary(Primary::Literal(literal)),
+                    BlockType::Identifier(ref identifier) => ExpressionType::Primary(Primary::Identifier(identifier)),
+                    _ => return Err(Error::new(0, 0, ErrorType::Unknown))
+                }
+            });
+        }
+
+        self.list()
+    }
+
+    // The embedded code of an interpolated string is parsed on its own, as a block
+    fn interpolation(&mut self, block: &'a Block, parts: &'a [StringPart]) -> ExpressionResult<'a> {
+        let mut expressions = Vec::new();
+
+        for part in parts {
+            expressions.push(match part {
+                StringPart::Text { literal, offset, width } => Expression {
+                    offset: *offset,
+                    width: *width,
+                    content: "",
+                    expression_type: ExpressionType::Primary(Primary::Literal(literal))
+                },
+                StringPart::Code { blocks, offset, width } => Expression {
+                    offset: *offset,
+                    width: *width,
+                    content: "",
+                    expression_type: ExpressionType::Block(Parser::new().parse(blocks)?)
+                }
+            });
+        }
+
+        Ok(Expression {
+            offset: block.offset,
+            width: block.width,
+            content: &block.content,
+            expression_type: ExpressionType::Interpolation(expressions)
+        })
+    }
+
+    fn list(&mut self) -> ExpressionResult<'a> {
+        if let Some(open) = self.get(&[Token::BraceOpen]) {
+            let mut values = Vec::new();
             let closed;
 
             loop {
                 if self.is_end() {
-                    return Err(Error::new(open.offset, open.width, ErrorType::ParserError(ParserErrorType::UnclosedParenthesis)));
+                    return Err(Error::new(open.offset, open.width, ErrorType::ParserError(ParserErrorType::UnclosedBrace)));
                 }
 
-                if let Some(par) = self.get(&[Token::ParClosed]) {
-                    closed = par;
+                if let Some(brace) = self.get(&[Token::BraceClosed]) {
+                    closed = brace;
                     break;
                 }
 
                 if let Some(comma) = self.get(&[Token::Comma]) {
-                    args.push(Box::new(Expression {
+                    values.push(Box::new(Expression {
                         offset: comma.offset,
                         width: comma.width,
                         content: &comma.content,
                         expression_type: ExpressionType::Primary(Primary::Literal(&Literal::Null))
                     }));
+                } else if let Some(dots) = self.get(&[Token::TripleDot]) {
+                    values.push(Box::new(self.spread(dots)?));
+                    self.get(&[Token::Comma]);
                 } else {
-                    args.push(Box::new(self.expression()?));
+                    values.push(Box::new(self.expression()?));
                     self.get(&[Token::Comma]);
                 }
             }
 
-            // Remove arguments in the case of empty arguments function call, like a()
-            if args.len() == 1 {
-                if let ExpressionType::Primary(Primary::Literal(&Literal::Null)) = args[0].expression_type {
-                    args.pop();
+            return Ok(Expression {
+                offset: open.offset,
+                width: closed.offset + closed.width - open.offset,
+                content: "",
+                expression_type: ExpressionType::List(values)
+            });
+        }
+
+        self.map()
+    }
+
+    // The expression following ..., in a list or the arguments of a call
+    fn spread(&mut self, dots: &'a Block) -> ExpressionResult<'a> {
+        let expr = self.expression()?;
+
+        Ok(Expression {
+            offset: dots.offset,
+            width: expr.offset + expr.width - dots.offset,
+            content: &dots.content,
+            expression_type: ExpressionType::Spread(Box::new(expr))
+        })
+    }
+
+    // Whether the upcoming bracket opens a map literal like { key: ... }, rather than a block
+    fn is_map(&self) -> bool {
+        let is_key = |block: &Block| matches!(block.block_type, BlockType::Identifier(_) | BlockType::Literal(Literal::String(_)));
+
+        self.check(Token::BracketOpen).is_some() && match (self.get_at(self.index + 2), self.get_at(self.index + 3)) {
+            (Some(key), Some(colon)) => is_key(key) && colon.token == Token::Colon,
+            _ => false
+        }
+    }
+
+    fn map(&mut self) -> ExpressionResult<'a> {
+        if let Some(open) = self.get(&[Token::BracketOpen]) {
+            let mut pairs = Vec::new();
+            let closed;
+
+            loop {
+                if self.is_end() {
+                    return Err(Error::new(open.offset, open.width, ErrorType::ParserError(ParserErrorType::UnclosedBracket)));
                 }
-            }
 
-            expr = Expression {
-                offset: expr.offset,
-                width: closed.offset - expr.offset + 1,
-                content: expr.content,
-                expression_type: ExpressionType::FunctionCall {
-                    func: Box::new(expr),
-                    args,
+                if let Some(bracket) = self.get(&[Token::BracketClosed]) {
+                    closed = bracket;
+                    break;
                 }
-            }
-        }
 
-        Ok(expr)
-    }
+                let key: &'a str = match self.peek().map(|v| &v.block_type) {
+                    Some(BlockType::Identifier(key)) | Some(BlockType::Literal(Literal::String(key))) => key,
+                    _ => return Err(self.unexpected().with_help(String::from("map keys are identifiers or strings")))
+                };
+                self.advance();
 
-    fn primary(&mut self) -> ExpressionResult<'a> {
-        if let Some(block) = self.get(&[Token::Literal, Token::Identifier]) {
-            return Ok(Expression {
-                offset: block.offset,
-                width: block.width,
-                content: &block.content,
-                expression_type: match block.block_type {
-                    BlockType::Literal(ref literal) => ExpressionType::Primary(Primary::Literal(literal)),
-                    BlockType::Identifier(ref identifier) => ExpressionType::Primary(Primary::Identifier(identifier)),
-                    _ => return Err(Error::new(0, 0, ErrorType::Unknown))
+                if self.get(&[Token::Colon]).is_none() {
+                    return Err(self.unexpected().with_help(String::from("expected ':' after the map key")));
                 }
+
+                pairs.push((key, Box::new(self.expression()?)));
+
+                if self.get(&[Token::Comma]).is_none()
+                    && self.check(Token::BracketClosed).is_none() {
+                        return Err(self.unexpected());
+                    }
+            }
+
+            return Ok(Expression {
+                offset: open.offset,
+                width: closed.offset + closed.width - open.offset,
+                content: "",
+                expression_type: ExpressionType::Map(pairs)
             });
         }
 
-        return self.parenthesis();
+        self.parenthesis()
     }
 
     fn parenthesis(&mut self) -> ExpressionResult<'a> {
         if let Some(parenthesis) = self.get(&[Token::ParOpen]) {
             let expr = self.expression()?;
-            if let None = self.get(&[Token::ParClosed]) {
+            if self.get(&[Token::ParClosed]).is_none() {
                 return Err(Error::new(
                     parenthesis.offset,
                     parenthesis.width,
@@ -448,30 +1055,30 @@ impl<'a> Parser<'a> {
             return Ok(expr);
         }
 
-        return self.empty();
+        self.empty()
     }
 
     fn empty(&mut self) -> ExpressionResult<'a> {
+        Err(self.unexpected())
+    }
+
+    fn unexpected(&self) -> Error {
         let (offset, width) = self.peek()
             .map(|v| (v.offset, v.width))
-            .or(Some((0, 0)))
-            .unwrap();
+            .unwrap_or((0, 0));
         
-        return Err(
-            Error::new(offset, width, ErrorType::ParserError(ParserErrorType::UnexpectedToken))
-                .with_description(format!(
-                    "Did not expect token [{}]",
-                    self.peek()
-                        .map(|v| format!("{:?}", v.block_type))
-                        .or(Some(String::from("Unknown block")))
-                        .unwrap()
-                ))
-        );
+        Error::new(offset, width, ErrorType::ParserError(ParserErrorType::UnexpectedToken))
+            .with_description(format!(
+                "Did not expect token [{}]",
+                self.peek()
+                    .map(|v| format!("{:?}", v.block_type))
+                    .unwrap_or(String::from("Unknown block"))
+            ))
     }
 
     pub fn parse(&mut self, lexed: &'a LinkedList<Block>) -> Result<AST<'a>, Error> {
         self.index = 0;
-        self.lexed = lexed.into_iter()
+        self.lexed = lexed.iter()
             .collect::<Vec<&'a Block>>();
 
         let res = self.ast()?;
@@ -479,6 +1086,6 @@ impl<'a> Parser<'a> {
         self.lexed = Vec::new();
         self.index = 0;
 
-        return Ok(res);
+        Ok(res)
     }
 }
\ No newline at end of file
@@ -0,0 +1,356 @@
+use std::collections::HashMap;
+
+use super::error::*;
+use super::parser::*;
+use super::lexer::*;
+
+// Checks the names a program assigns to, between parsing and compiling
+pub struct Resolver {
+    strict: bool, // Assigning an undeclared name is an error, rather than declaring it
+    globals: HashMap<String, bool>, // Names declared by earlier programs, like earlier lines in the shell
+    resolved: HashMap<String, bool> // Names declared up to the last resolved program, kept once it has run
+}
+
+struct Scope {
+    parent: Option<usize>,
+    function: bool, // Names assigned without being declared end up in the nearest function scope
+    variables: HashMap<String, bool> // Whether each name is a constant
+}
+
+struct Resolution<'r, 'a> {
+    strict: bool,
+    scopes: Vec<Scope>,
+    functions: Vec<(usize, &'r Expression<'a>)> // Function bodies, with the scope they were defined in
+}
+
+impl Default for Resolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Resolver {
+    pub fn new() -> Self {
+        Resolver {
+            strict: false,
+            globals: HashMap::new(),
+            resolved: HashMap::new()
+        }
+    }
+
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    pub fn resolve(&mut self, ast: &AST) -> Result<(), Error> {
+        let mut resolution = Resolution {
+            strict: self.strict,
+            scopes: vec![Scope {
+                parent: None,
+                function: true,
+                variables: self.globals.clone()
+            }],
+            functions: Vec::new()
+        };
+
+        resolution.ast(ast, 0)?;
+
+        // Bodies are resolved last, as they run after the names declared below them
+        let mut i = 0;
+        while i < resolution.functions.len() {
+            let (scope, function) = resolution.functions[i];
+            resolution.function(function, scope)?;
+            i += 1;
+        }
+
+        self.resolved = resolution.scopes.swap_remove(0).variables;
+        Ok(())
+    }
+
+    // Keeps the names declared by the last resolved program, once it ran without errors
+    pub fn commit(&mut self) {
+        self.globals = std::mem::take(&mut self.resolved);
+    }
+}
+
+fn is_assignment(operator: Token) -> bool {
+    matches!(
+        operator,
+        Token::Equals |
+        Token::PlusEquals |
+        Token::MinusEquals |
+        Token::AsterixEquals |
+        Token::FSlashEquals |
+        Token::PercentEquals |
+        Token::DoubleAsterixEquals
+    )
+}
+
+impl<'r, 'a> Resolution<'r, 'a> {
+    fn scope(&mut self, parent: usize, function: bool) -> usize {
+        self.scopes.push(Scope {
+            parent: Some(parent),
+            function,
+            variables: HashMap::new()
+        });
+
+        self.scopes.len() - 1
+    }
+
+    // Whether the name is a constant, or None if it is not declared
+    fn lookup(&self, identifier: &str, scope: usize) -> Option<bool> {
+        let scope = &self.scopes[scope];
+
+        scope.variables.get(identifier)
+            .copied()
+            .or_else(|| scope.parent.and_then(|parent| self.lookup(identifier, parent)))
+    }
+
+    fn declare(&mut self, pattern: &Pattern, constant: bool, scope: usize) {
+        match pattern {
+            Pattern::Identifier(identifier) => {
+                self.scopes[scope].variables.insert(String::from(*identifier), constant);
+            },
+            Pattern::List { items, rest, .. } => {
+                for item in items {
+                    self.declare(item, constant, scope);
+                }
+
+                if let Some(rest) = rest {
+                    self.scopes[scope].variables.insert(String::from(*rest), constant);
+                }
+            }
+        }
+    }
+
+    // Constants can be shadowed in inner scopes, but not replaced in their own
+    fn check_redeclared(&self, pattern: &Pattern, declaration: &Declaration, scope: usize) -> Result<(), Error> {
+        let check = |identifier: &str| match self.scopes[scope].variables.get(identifier) {
+            Some(true) => Err(
+                Error::new(declaration.offset, declaration.width, ErrorType::ResolverError(ResolverErrorType::RedeclaredConstant {
+                    identifier: String::from(identifier)
+                }))
+                    .with_description(format!("[{}] is already declared as a constant in this scope", identifier))
+                    .with_help(String::from("use another name, or declare it within a block to shadow it"))
+            ),
+            _ => Ok(())
+        };
+
+        match pattern {
+            Pattern::Identifier(identifier) => check(identifier),
+            Pattern::List { items, rest, .. } => {
+                for item in items {
+                    self.check_redeclared(item, declaration, scope)?;
+                }
+
+                match rest {
+                    Some(rest) => check(rest),
+                    None => Ok(())
+                }
+            }
+        }
+    }
+
+    fn assign(&mut self, identifier: &str, expr: &Expression, scope: usize) -> Result<(), Error> {
+        match self.lookup(identifier, scope) {
+            Some(true) => Err(
+                Error::new(expr.offset, expr.width, ErrorType::ResolverError(ResolverErrorType::AssignToConstant {
+                    identifier: String::from(identifier)
+                }))
+                    .with_description(format!("[{}] is a constant, and can not be assigned to", identifier))
+                    .with_help(String::from("declare it with let to be able to assign to it"))
+            ),
+            Some(false) => Ok(()),
+            None if self.strict => Err(
+                Error::new(expr.offset, expr.width, ErrorType::ResolverError(ResolverErrorType::UndeclaredVariable {
+                    identifier: String::from(identifier)
+                }))
+                    .with_description(format!("[{}] is assigned to without being declared", identifier))
+                    .with_help(format!("declare it first, like let {} = ...", identifier))
+            ),
+            None => {
+                // Like in the VM, where the name is set in the nearest function scope
+                let mut function = scope;
+                while !self.scopes[function].function {
+                    function = self.scopes[function].parent.unwrap();
+                }
+
+                self.scopes[function].variables.insert(String::from(identifier), false);
+                Ok(())
+            }
+        }
+    }
+
+    fn assign_pattern(&mut self, pattern: &Pattern, expr: &Expression, scope: usize) -> Result<(), Error> {
+        match pattern {
+            Pattern::Identifier(identifier) => self.assign(identifier, expr, scope),
+            Pattern::List { items, rest, .. } => {
+                for item in items {
+                    self.assign_pattern(item, expr, scope)?;
+                }
+
+                match rest {
+                    Some(rest) => self.assign(rest, expr, scope),
+                    None => Ok(())
+                }
+            }
+        }
+    }
+
+    fn ast(&mut self, ast: &'r AST<'a>, scope: usize) -> Result<(), Error> {
+        for declaration in ast {
+            self.declaration(declaration, scope)?;
+        }
+
+        Ok(())
+    }
+
+    fn declaration(&mut self, declaration: &'r Declaration<'a>, scope: usize) -> Result<(), Error> {
+        match &declaration.declaration_type {
+            DeclarationType::Statement(statement) => self.statement(statement, scope),
+            DeclarationType::Variable { constant, pattern, value } => {
+                // The value can not refer to the names it is declaring, as they are not declared yet
+                if let Some(value) = value {
+                    self.expression(value, scope)?;
+                }
+
+                self.check_redeclared(pattern, declaration, scope)?;
+                self.declare(pattern, *constant, scope);
+                Ok(())
+            }
+        }
+    }
+
+    fn statement(&mut self, statement: &'r Statement<'a>, scope: usize) -> Result<(), Error> {
+        match &statement.statement_type {
+            StatementType::Expression(expr) => self.expression(expr, scope),
+            StatementType::While { condition, body } => {
+                self.expression(condition, scope)?;
+
+                let body_scope = self.scope(scope, false);
+                self.ast(body, body_scope)
+            },
+            StatementType::For { identifier, iterable, body } => {
+                self.expression(iterable, scope)?;
+
+                let body_scope = self.scope(scope, false);
+                self.declare(&Pattern::Identifier(identifier), false, body_scope);
+                self.ast(body, body_scope)
+            },
+            StatementType::Return(Some(expr)) => self.expression(expr, scope),
+            StatementType::Return(None) | StatementType::Break | StatementType::Continue => Ok(())
+        }
+    }
+
+    fn expression(&mut self, expr: &'r Expression<'a>, scope: usize) -> Result<(), Error> {
+        match &expr.expression_type {
+            ExpressionType::Binary { left, right, operator, .. } if is_assignment(*operator) => {
+                self.expression(right, scope)?;
+
+                match &left.expression_type {
+                    ExpressionType::Primary(Primary::Identifier(identifier)) => self.assign(identifier, left, scope),
+                    _ => self.expression(left, scope)
+                }
+            },
+            ExpressionType::Binary { left, right, .. } => {
+                self.expression(left, scope)?;
+                self.expression(right, scope)
+            },
+            ExpressionType::Destructure { pattern, value } => {
+                self.expression(value, scope)?;
+                self.assign_pattern(pattern, expr, scope)
+            },
+            ExpressionType::Function { .. } => {
+                self.functions.push((scope, expr));
+                Ok(())
+            },
+            ExpressionType::FunctionCall { func, args, named } => {
+                self.expression(func, scope)?;
+                for arg in args {
+                    self.expression(arg, scope)?;
+                }
+
+                for (_, arg) in named {
+                    self.expression(arg, scope)?;
+                }
+
+                Ok(())
+            },
+            ExpressionType::List(items) => {
+                for item in items {
+                    self.expression(item, scope)?;
+                }
+
+                Ok(())
+            },
+            ExpressionType::Interpolation(parts) => {
+                for part in parts {
+                    self.expression(part, scope)?;
+                }
+
+                Ok(())
+            },
+            ExpressionType::Map(pairs) => {
+                for (_, value) in pairs {
+                    self.expression(value, scope)?;
+                }
+
+                Ok(())
+            },
+            ExpressionType::ListIndex { list, index } => {
+                self.expression(list, scope)?;
+                self.expression(index, scope)
+            },
+            ExpressionType::Slice { list, start, end } => {
+                self.expression(list, scope)?;
+                for bound in [start, end].iter().copied().flatten() {
+                    self.expression(bound, scope)?;
+                }
+
+                Ok(())
+            },
+            ExpressionType::Member { object, .. } => self.expression(object, scope),
+            ExpressionType::Spread(expr) => self.expression(expr, scope),
+            ExpressionType::Unary { expr, .. } => self.expression(expr, scope),
+            ExpressionType::Block(ast) => {
+                let block_scope = self.scope(scope, false);
+                self.ast(ast, block_scope)
+            },
+            ExpressionType::If { condition, body, else_body } => {
+                self.expression(condition, scope)?;
+                self.expression(body, scope)?;
+
+                match else_body {
+                    Some(else_body) => self.expression(else_body, scope),
+                    None => Ok(())
+                }
+            },
+            ExpressionType::Primary(_) | ExpressionType::Empty => Ok(())
+        }
+    }
+
+    fn function(&mut self, expr: &'r Expression<'a>, scope: usize) -> Result<(), Error> {
+        if let ExpressionType::Function { pars, rest, body } = &expr.expression_type {
+            let function_scope = self.scope(scope, true);
+
+            // Defaults are evaluated once all the passed arguments are bound
+            for par in pars {
+                self.declare(&par.pattern, false, function_scope);
+            }
+
+            if let Some(rest) = rest {
+                self.declare(&Pattern::Identifier(rest), false, function_scope);
+            }
+
+            for par in pars {
+                if let Some(default) = &par.default {
+                    self.expression(default, function_scope)?;
+                }
+            }
+
+            self.ast(body, function_scope)?;
+        }
+
+        Ok(())
+    }
+}
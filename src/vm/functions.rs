@@ -1,26 +1,65 @@
 use std::rc::Rc;
 use std::cell::RefCell;
 
-use super::{VMInstance, Value, Error};
+use super::{VMInstance, Value, Error, ErrorType, VMErrorType, Instruction, List, invalid_cast};
 
 type NativeInstance = Rc<RefCell<VMInstance>>;
 type NativeValue = Rc<Value>;
 type NativeReturn = Result<NativeValue, Error>;
 
-pub type NativeFunction = fn(NativeInstance, Vec<NativeValue>) -> NativeReturn;
+pub type NativeFunction = fn(NativeInstance, &Instruction, Vec<NativeValue>) -> NativeReturn;
 
 const NULL: Value = Value::Null;
 
-pub fn print_value(_instance: NativeInstance, args: Vec<NativeValue>) -> NativeReturn {
-    for arg in args {
-        print!("{:?} ", arg);
+pub fn expect_args(instruction: &Instruction, args: &[NativeValue], count: usize) -> Result<(), Error> {
+    if args.len() != count {
+        return Err(
+            Error::new(instruction.offset, instruction.width, ErrorType::VMError(VMErrorType::MismatchedArgumentCount))
+                .with_description(format!("Expected {} arguments, got {}", count, args.len()))
+        );
     }
 
-    println!("");
+    Ok(())
+}
+
+pub fn print_value(_instance: NativeInstance, _instruction: &Instruction, args: Vec<NativeValue>) -> NativeReturn {
+    println!("{}", args.iter()
+        .map(|arg| arg.to_string())
+        .collect::<Vec<String>>()
+        .join(" ")
+    );
 
     Ok(Rc::from(NULL))
 }
 
+// The keys of a map, in sorted order
+pub fn keys(_instance: NativeInstance, instruction: &Instruction, args: Vec<NativeValue>) -> NativeReturn {
+    expect_args(instruction, &args, 1)?;
+
+    match &*args[0] {
+        Value::Map(map) => Ok(Rc::from(Value::List(List::new(
+            map.items().keys()
+                .map(|key| Rc::from(Value::String(key.clone())))
+                .collect()
+        )))),
+        _ => Err(invalid_cast(instruction, &args[0], "Map"))
+    }
+}
+
+// Whether a map has the key, or a list contains the value
+pub fn has(_instance: NativeInstance, instruction: &Instruction, args: Vec<NativeValue>) -> NativeReturn {
+    expect_args(instruction, &args, 2)?;
+
+    let res = match (&*args[0], &*args[1]) {
+        (Value::Map(map), Value::String(key)) => map.items().contains_key(key),
+        (Value::Map(_), key) => return Err(invalid_cast(instruction, key, "String")),
+        (Value::List(list), value) => list.items().iter().any(|v| **v == *value),
+        (value, _) => return Err(invalid_cast(instruction, value, "Map or List"))
+    };
+
+    Ok(Rc::from(Value::Bool(res)))
+}
+
 // pub fn range(instance: NativeInstance, args: NativeArgs) {
     
-// }
\ No newline at end of file
+// }
@@ -0,0 +1,73 @@
+use std::rc::Rc;
+use std::cell::{RefCell, Ref, RefMut};
+use std::collections::HashSet;
+use std::fmt;
+
+use super::Value;
+
+thread_local! {
+    // Lists and maps being shown or compared further up the call stack
+    static VISITING: RefCell<HashSet<(usize, usize)>> = RefCell::new(HashSet::new());
+}
+
+// Lists and maps can contain themselves, like after xs[0] = xs. Calls f unless the containers
+// are already being visited, in which case None is returned instead of recursing forever
+pub fn visit<T>(containers: (usize, usize), f: impl FnOnce() -> T) -> Option<T> {
+    if !VISITING.with(|visiting| visiting.borrow_mut().insert(containers)) {
+        return None;
+    }
+
+    let res = f();
+    VISITING.with(|visiting| visiting.borrow_mut().remove(&containers));
+    Some(res)
+}
+
+pub fn address<T>(container: &T) -> usize {
+    container as *const T as usize
+}
+
+// Shared by every reference to the list, so it can be modified in place
+pub struct List {
+    items: RefCell<Vec<Rc<Value>>>
+}
+
+impl List {
+    pub fn new(items: Vec<Rc<Value>>) -> Self {
+        List {
+            items: RefCell::from(items)
+        }
+    }
+
+    pub fn items(&self) -> Ref<'_, Vec<Rc<Value>>> {
+        self.items.borrow()
+    }
+
+    pub fn items_mut(&self) -> RefMut<'_, Vec<Rc<Value>>> {
+        self.items.borrow_mut()
+    }
+
+    pub fn len(&self) -> usize {
+        self.items().len()
+    }
+
+    pub fn get(&self, index: usize) -> Option<Rc<Value>> {
+        self.items().get(index).map(Rc::clone)
+    }
+}
+
+impl fmt::Debug for List {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match visit((address(self), 0), || self.items().fmt(f)) {
+            Some(res) => res,
+            None => write!(f, "[...]")
+        }
+    }
+}
+
+// A list containing itself is never equal to another list
+impl PartialEq for List {
+    fn eq(&self, other: &List) -> bool {
+        visit((address(self), address(other)), || *self.items() == *other.items()).unwrap_or(false)
+    }
+}
+
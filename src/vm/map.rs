@@ -0,0 +1,47 @@
+use std::rc::Rc;
+use std::cell::{RefCell, Ref, RefMut};
+use std::collections::BTreeMap;
+use std::fmt;
+
+use super::Value;
+use super::list::{visit, address};
+
+// Shared by every reference to the map like a List, keys are kept sorted
+pub struct Map {
+    items: RefCell<BTreeMap<String, Rc<Value>>>
+}
+
+impl Map {
+    pub fn new(items: BTreeMap<String, Rc<Value>>) -> Self {
+        Map {
+            items: RefCell::from(items)
+        }
+    }
+
+    pub fn items(&self) -> Ref<'_, BTreeMap<String, Rc<Value>>> {
+        self.items.borrow()
+    }
+
+    pub fn items_mut(&self) -> RefMut<'_, BTreeMap<String, Rc<Value>>> {
+        self.items.borrow_mut()
+    }
+
+    pub fn get(&self, key: &str) -> Option<Rc<Value>> {
+        self.items().get(key).map(Rc::clone)
+    }
+}
+
+impl fmt::Debug for Map {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match visit((address(self), 0), || self.items().fmt(f)) {
+            Some(res) => res,
+            None => write!(f, "{{...}}")
+        }
+    }
+}
+
+impl PartialEq for Map {
+    fn eq(&self, other: &Map) -> bool {
+        visit((address(self), address(other)), || *self.items() == *other.items()).unwrap_or(false)
+    }
+}
@@ -0,0 +1,166 @@
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use super::{VMInstance, Value, Error, ErrorType, VMErrorType, Instruction, List, invalid_cast};
+use super::functions::expect_args;
+
+type NativeInstance = Rc<RefCell<VMInstance>>;
+type NativeValue = Rc<Value>;
+type NativeReturn = Result<NativeValue, Error>;
+
+pub type NativeMethod = fn(NativeInstance, &Instruction, NativeValue, Vec<NativeValue>) -> NativeReturn;
+
+const NULL: Value = Value::Null;
+
+lazy_static! {
+    // Built-in methods, keyed by the type name of the receiver and the method name
+    static ref METHODS: HashMap<(&'static str, &'static str), NativeMethod> = hashmap!{
+        ("String", "len") => string_len as NativeMethod,
+        ("String", "upper") => string_upper as NativeMethod,
+        ("String", "lower") => string_lower as NativeMethod,
+        ("String", "trim") => string_trim as NativeMethod,
+        ("String", "split") => string_split as NativeMethod,
+        ("String", "contains") => string_contains as NativeMethod,
+
+        ("List", "len") => list_len as NativeMethod,
+        ("List", "push") => list_push as NativeMethod,
+        ("List", "pop") => list_pop as NativeMethod,
+        ("List", "contains") => list_contains as NativeMethod,
+
+        ("Map", "len") => map_len as NativeMethod,
+        ("Map", "keys") => map_keys as NativeMethod,
+        ("Map", "has") => map_has as NativeMethod,
+        ("Map", "remove") => map_remove as NativeMethod
+    };
+}
+
+pub fn find(receiver: &Value, name: &str) -> Option<NativeMethod> {
+    METHODS.get(&(receiver.type_name(), name)).copied()
+}
+
+fn string(instruction: &Instruction, value: &Value) -> Result<String, Error> {
+    match value {
+        Value::String(s) => Ok(s.clone()),
+        _ => Err(invalid_cast(instruction, value, "String"))
+    }
+}
+
+// Lengths count characters, not bytes
+fn string_len(_instance: NativeInstance, instruction: &Instruction, receiver: NativeValue, args: Vec<NativeValue>) -> NativeReturn {
+    expect_args(instruction, &args, 0)?;
+    Ok(Rc::from(Value::Int(string(instruction, &receiver)?.chars().count() as i64)))
+}
+
+fn string_upper(_instance: NativeInstance, instruction: &Instruction, receiver: NativeValue, args: Vec<NativeValue>) -> NativeReturn {
+    expect_args(instruction, &args, 0)?;
+    Ok(Rc::from(Value::String(string(instruction, &receiver)?.to_uppercase())))
+}
+
+fn string_lower(_instance: NativeInstance, instruction: &Instruction, receiver: NativeValue, args: Vec<NativeValue>) -> NativeReturn {
+    expect_args(instruction, &args, 0)?;
+    Ok(Rc::from(Value::String(string(instruction, &receiver)?.to_lowercase())))
+}
+
+fn string_trim(_instance: NativeInstance, instruction: &Instruction, receiver: NativeValue, args: Vec<NativeValue>) -> NativeReturn {
+    expect_args(instruction, &args, 0)?;
+    Ok(Rc::from(Value::String(String::from(string(instruction, &receiver)?.trim()))))
+}
+
+fn string_split(_instance: NativeInstance, instruction: &Instruction, receiver: NativeValue, args: Vec<NativeValue>) -> NativeReturn {
+    expect_args(instruction, &args, 1)?;
+
+    let (s, separator) = (string(instruction, &receiver)?, string(instruction, &args[0])?);
+    Ok(Rc::from(Value::List(List::new(
+        s.split(separator.as_str())
+            .map(|part| Rc::from(Value::String(String::from(part))))
+            .collect()
+    ))))
+}
+
+fn string_contains(_instance: NativeInstance, instruction: &Instruction, receiver: NativeValue, args: Vec<NativeValue>) -> NativeReturn {
+    expect_args(instruction, &args, 1)?;
+
+    let (s, pattern) = (string(instruction, &receiver)?, string(instruction, &args[0])?);
+    Ok(Rc::from(Value::Bool(s.contains(pattern.as_str()))))
+}
+
+fn list_len(_instance: NativeInstance, instruction: &Instruction, receiver: NativeValue, args: Vec<NativeValue>) -> NativeReturn {
+    expect_args(instruction, &args, 0)?;
+
+    match &*receiver {
+        Value::List(list) => Ok(Rc::from(Value::Int(list.len() as i64))),
+        _ => Err(invalid_cast(instruction, &receiver, "List"))
+    }
+}
+
+fn list_push(_instance: NativeInstance, instruction: &Instruction, receiver: NativeValue, args: Vec<NativeValue>) -> NativeReturn {
+    expect_args(instruction, &args, 1)?;
+
+    match &*receiver {
+        Value::List(list) => {
+            list.items_mut().push(Rc::clone(&args[0]));
+            Ok(Rc::from(NULL))
+        },
+        _ => Err(invalid_cast(instruction, &receiver, "List"))
+    }
+}
+
+fn list_pop(_instance: NativeInstance, instruction: &Instruction, receiver: NativeValue, args: Vec<NativeValue>) -> NativeReturn {
+    expect_args(instruction, &args, 0)?;
+
+    match &*receiver {
+        Value::List(list) => match list.items_mut().pop() {
+            Some(val) => Ok(val),
+            None => Err(Error::new(instruction.offset, instruction.width, ErrorType::VMError(VMErrorType::IndexOutOfBounds {
+                list_size: 0,
+                index: -1
+            })))
+        },
+        _ => Err(invalid_cast(instruction, &receiver, "List"))
+    }
+}
+
+fn list_contains(_instance: NativeInstance, instruction: &Instruction, receiver: NativeValue, args: Vec<NativeValue>) -> NativeReturn {
+    expect_args(instruction, &args, 1)?;
+
+    match &*receiver {
+        Value::List(list) => Ok(Rc::from(Value::Bool(list.items().iter().any(|v| **v == *args[0])))),
+        _ => Err(invalid_cast(instruction, &receiver, "List"))
+    }
+}
+
+fn map_len(_instance: NativeInstance, instruction: &Instruction, receiver: NativeValue, args: Vec<NativeValue>) -> NativeReturn {
+    expect_args(instruction, &args, 0)?;
+
+    match &*receiver {
+        Value::Map(map) => Ok(Rc::from(Value::Int(map.items().len() as i64))),
+        _ => Err(invalid_cast(instruction, &receiver, "Map"))
+    }
+}
+
+fn map_keys(instance: NativeInstance, instruction: &Instruction, receiver: NativeValue, args: Vec<NativeValue>) -> NativeReturn {
+    expect_args(instruction, &args, 0)?;
+    super::functions::keys(instance, instruction, vec![receiver])
+}
+
+fn map_has(instance: NativeInstance, instruction: &Instruction, receiver: NativeValue, args: Vec<NativeValue>) -> NativeReturn {
+    expect_args(instruction, &args, 1)?;
+    super::functions::has(instance, instruction, vec![receiver, Rc::clone(&args[0])])
+}
+
+// Removes the key, and returns the value it held
+fn map_remove(_instance: NativeInstance, instruction: &Instruction, receiver: NativeValue, args: Vec<NativeValue>) -> NativeReturn {
+    expect_args(instruction, &args, 1)?;
+
+    let key = string(instruction, &args[0])?;
+    match &*receiver {
+        Value::Map(map) => match map.items_mut().remove(&key) {
+            Some(val) => Ok(val),
+            None => Err(Error::new(instruction.offset, instruction.width, ErrorType::VMError(VMErrorType::KeyNotFound {
+                key
+            })))
+        },
+        _ => Err(invalid_cast(instruction, &receiver, "Map"))
+    }
+}
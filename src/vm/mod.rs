@@ -1,5 +1,7 @@
 use std::rc::Rc;
 use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::convert::TryFrom;
 
 use super::error::{Error, ErrorType, VMErrorType};
 use super::compiler::{Program, Code, Instruction};
@@ -8,14 +10,24 @@ mod scope;
 mod stack;
 mod pool;
 mod functions;
+mod methods;
+mod list;
+mod map;
+
+#[cfg(test)]
+mod test;
 
 use scope::Scope;
 use stack::Stack;
 use pool::Pool;
 use functions::NativeFunction;
+use methods::NativeMethod;
+use list::List;
+use map::Map;
 
 const STACK_SIZE: usize = 512;
 const GC_INSTRUCTION_COUNT: usize = 50; // At which amount of instructions to run the GC
+const MAX_STRING_LENGTH: usize = 1 << 26; // In bytes, longer strings built by operators are an error
 
 const NULL: Value = Value::Null;
 
@@ -39,26 +51,294 @@ fn invalid_cast(instruction: &Instruction, value: &Value, to: &'static str) -> E
         .with_description(format!("Value [{:?}] could not be cast to [{}] type", value, to))
 }
 
+fn assign_to_constant(instruction: &Instruction, identifier: &str) -> Error {
+    Error::new(instruction.offset, instruction.width, ErrorType::VMError(VMErrorType::AssignToConstant {
+        identifier: String::from(identifier)
+    }))
+        .with_description(format!("[{}] is a constant, and can not be assigned to", identifier))
+}
+
+fn string_too_large(instruction: &Instruction, length: Option<usize>) -> Error {
+    Error::new(instruction.offset, instruction.width, ErrorType::VMError(VMErrorType::StringTooLarge))
+        .with_description(match length {
+            Some(length) => format!("The resulting string of {} bytes is longer than the limit of {} bytes", length, MAX_STRING_LENGTH),
+            None => format!("The resulting string is longer than the limit of {} bytes", MAX_STRING_LENGTH)
+        })
+}
+
+fn integer_overflow(instruction: &Instruction, first: i64, second: i64) -> Error {
+    Error::new(instruction.offset, instruction.width, ErrorType::VMError(VMErrorType::IntegerOverflow))
+        .with_description(format!("Operation [{:?}] overflowed for operands [{}] and [{}]", instruction.code, first, second))
+}
+
+fn index_out_of_bounds(instruction: &Instruction, len: usize, index: i64) -> Error {
+    Error::new(instruction.offset, instruction.width, ErrorType::VMError(VMErrorType::IndexOutOfBounds {
+        list_size: len as i64,
+        index
+    }))
+}
+
+// Negative indices count from the end, so -1 is the last item
+fn from_end(len: usize, index: i64) -> i64 {
+    if index < 0 { index + len as i64 } else { index }
+}
+
+// Lists and strings are both indexed by position, strings by character rather than byte
+fn list_index(instruction: &Instruction, len: usize, index: &Value) -> Result<usize, Error> {
+    let index: i64 = match index {
+        Value::Int(i) => *i,
+        _ => return Err(invalid_cast(instruction, index, "Int"))
+    };
+
+    let position = from_end(len, index);
+    if position < 0 || position >= len as i64 {
+        return Err(index_out_of_bounds(instruction, len, index));
+    }
+
+    Ok(position as usize)
+}
+
+// A left out start or end, given as null, is the start or end of the list
+fn slice_range(instruction: &Instruction, len: usize, start: &Value, end: &Value) -> Result<(usize, usize), Error> {
+    let bound = |bound: &Value, default: usize| match bound {
+        Value::Null => Ok((default as i64, default as i64)),
+        Value::Int(i) => Ok((*i, from_end(len, *i))),
+        _ => Err(invalid_cast(instruction, bound, "Int"))
+    };
+
+    let ((start, start_position), (end, end_position)) = (bound(start, 0)?, bound(end, len)?);
+
+    if start_position < 0 || start_position > len as i64 {
+        return Err(index_out_of_bounds(instruction, len, start));
+    }
+
+    if end_position < start_position || end_position > len as i64 {
+        return Err(index_out_of_bounds(instruction, len, end));
+    }
+
+    Ok((start_position as usize, end_position as usize))
+}
+
+// Matches the arguments of a call to the parameters of the function, by position and then by name.
+// Parameters with defaults may be left out, and get their value in the function body
+fn bind_arguments(
+    instruction: &Instruction,
+    pars: &[(String, bool)],
+    rest: &Option<String>,
+    named: &[String],
+    mut args: Vec<Rc<Value>>
+) -> Result<Vec<(String, Rc<Value>)>, Error> {
+    let error = |error_type: VMErrorType, description: String| Error::new(instruction.offset, instruction.width, ErrorType::VMError(error_type))
+        .with_description(description);
+
+    let named_args = args.split_off(args.len() - named.len());
+    let mut bound: Vec<Option<Rc<Value>>> = vec![None; pars.len()];
+    let mut remaining = Vec::new();
+
+    for (index, arg) in args.into_iter().enumerate() {
+        match (bound.get_mut(index), rest) {
+            (Some(slot), _) => *slot = Some(arg),
+            (None, Some(_)) => remaining.push(arg),
+            (None, None) => return Err(error(
+                VMErrorType::UnexpectedArgument { index },
+                format!("Unexpected argument [{:?}], the function takes {} parameters", arg, pars.len())
+            ))
+        }
+    }
+
+    for (name, arg) in named.iter().zip(named_args) {
+        let index = match pars.iter().position(|(par, _)| par == name) {
+            Some(index) => index,
+            None => return Err(error(
+                VMErrorType::UnknownParameter { parameter: name.clone() },
+                format!("The function has no parameter [{}]", name)
+            ))
+        };
+
+        if bound[index].is_some() {
+            return Err(error(
+                VMErrorType::DuplicateArgument { parameter: name.clone() },
+                format!("The parameter [{}] was given more than once", name)
+            ));
+        }
+
+        bound[index] = Some(arg);
+    }
+
+    let mut bindings = Vec::new();
+    for ((name, default), arg) in pars.iter().zip(bound) {
+        match (arg, default) {
+            (Some(arg), _) => bindings.push((name.clone(), arg)),
+            (None, true) => {},
+            (None, false) => return Err(error(
+                VMErrorType::MissingArgument { parameter: name.clone() },
+                format!("Missing an argument for the parameter [{}]", name)
+            ))
+        }
+    }
+
+    if let Some(rest) = rest {
+        bindings.push((rest.clone(), Rc::from(Value::List(List::new(remaining)))));
+    }
+
+    Ok(bindings)
+}
+
+fn map_key(instruction: &Instruction, key: &Value) -> Result<String, Error> {
+    match key {
+        Value::String(key) => Ok(key.clone()),
+        _ => Err(invalid_cast(instruction, key, "String"))
+    }
+}
+
 #[derive(Debug)]
 pub enum Value {
     Null,
 
-    Int(i32),
+    Bool(bool),
+    Int(i64),
     Float(f64),
     String(String),
-    List(Vec<Rc<Value>>),
+    List(List),
+    Map(Map),
+    Range {
+        start: i64,
+        end: i64
+    },
+    Iterator {
+        iterable: Rc<Value>,
+        index: usize
+    },
 
     Variable {
         identifier: String,
     },
     Function {
-        position: usize
+        position: usize,
+        scope: Rc<RefCell<Scope>> // The scope the function was defined in
     },
     NativeFunction {
         function: NativeFunction
+    },
+    Method {
+        receiver: Rc<Value>, // The value the method was looked up on
+        method: NativeMethod
+    }
+}
+
+impl Value {
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Value::Null => false,
+            Value::Bool(b) => *b,
+            Value::Int(i) => *i != 0,
+            Value::Float(f) => *f != 0.0,
+            _ => true
+        }
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Null => "Null",
+            Value::Bool(_) => "Bool",
+            Value::Int(_) => "Int",
+            Value::Float(_) => "Float",
+            Value::String(_) => "String",
+            Value::List(_) => "List",
+            Value::Map(_) => "Map",
+            Value::Range { .. } => "Range",
+            Value::Iterator { .. } => "Iterator",
+            Value::Variable { .. } => "Variable",
+            Value::Function { .. } |
+            Value::NativeFunction { .. } |
+            Value::Method { .. } => "Function"
+        }
     }
 }
 
+// How values are shown to the user, like in interpolated strings and print
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        // Strings are only quoted within lists and maps
+        let item = |val: &Rc<Value>| match &**val {
+            Value::String(s) => format!("{:?}", s),
+            val => val.to_string()
+        };
+
+        match self {
+            Value::Null => write!(f, "null"),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Int(i) => write!(f, "{}", i),
+            Value::Float(x) => write!(f, "{:?}", x),
+            Value::String(s) => write!(f, "{}", s),
+            // Lists and maps containing themselves show the inner occurrences as [...] and {...}
+            Value::List(list) => match list::visit((list::address(list), 0), || list.items().iter().map(item).collect::<Vec<String>>()) {
+                Some(items) => write!(f, "[{}]", items.join(", ")),
+                None => write!(f, "[...]")
+            },
+            Value::Map(map) => match list::visit((list::address(map), 0), || map.items().iter()
+                .map(|(key, val)| format!("{:?}: {}", key, item(val)))
+                .collect::<Vec<String>>()
+            ) {
+                Some(pairs) => write!(f, "{{{}}}", pairs.join(", ")),
+                None => write!(f, "{{...}}")
+            },
+            Value::Range { start, end } => write!(f, "{}..{}", start, end),
+            Value::Iterator { .. } => write!(f, "<iterator>"),
+            Value::Variable { identifier } => write!(f, "{}", identifier),
+            Value::Function { .. } |
+            Value::NativeFunction { .. } |
+            Value::Method { .. } => write!(f, "<function>")
+        }
+    }
+}
+
+// Ints and Floats are compared by value, Lists structurally
+impl PartialEq for Value {
+    fn eq(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Null, Value::Null) => true,
+            (Value::Bool(first), Value::Bool(second)) => first == second,
+            (Value::Int(first), Value::Int(second)) => first == second,
+            (Value::Float(first), Value::Float(second)) => first == second,
+            (Value::Int(first), Value::Float(second)) => *first as f64 == *second,
+            (Value::Float(first), Value::Int(second)) => *first == *second as f64,
+            (Value::String(first), Value::String(second)) => first == second,
+            (Value::List(first), Value::List(second)) => first == second,
+            (Value::Map(first), Value::Map(second)) => first == second,
+            (Value::Range { start: a, end: b }, Value::Range { start: c, end: d }) => a == c && b == d,
+            (Value::Function { position: a, scope: b }, Value::Function { position: c, scope: d }) => a == c && Rc::ptr_eq(b, d),
+            _ => false
+        }
+    }
+}
+
+// Strings are ordered lexicographically, Lists element by element.
+// None means the values are unordered, like NaN, while values of types that can not be ordered are an error
+fn ordering(instruction: &Instruction, first: &Value, second: &Value) -> Result<Option<Ordering>, Error> {
+    Ok(match (first, second) {
+        (Value::Int(first), Value::Int(second)) => first.partial_cmp(second),
+        (Value::Float(first), Value::Float(second)) => first.partial_cmp(second),
+        (Value::Int(first), Value::Float(second)) => (*first as f64).partial_cmp(second),
+        (Value::Float(first), Value::Int(second)) => first.partial_cmp(&(*second as f64)),
+        (Value::String(first), Value::String(second)) => first.partial_cmp(second),
+        // A list containing itself is unordered to the lists it is compared with
+        (Value::List(first), Value::List(second)) => return list::visit((list::address(first), list::address(second)), || {
+            let (first, second) = (first.items(), second.items());
+
+            for (a, b) in first.iter().zip(second.iter()) {
+                match ordering(instruction, a, b)? {
+                    Some(Ordering::Equal) => {},
+                    other => return Ok(other)
+                }
+            }
+
+            Ok(first.len().partial_cmp(&second.len()))
+        }).unwrap_or(Ok(None)),
+        _ => return Err(operation_not_supported(instruction, first, second))
+    })
+}
+
 pub struct VM {
     root_pool: Rc<RefCell<Pool>>,
     root_stack: Rc<RefCell<Stack>>,
@@ -66,6 +346,12 @@ pub struct VM {
     root_instance: Option<VMInstance>
 }
 
+impl Default for VM {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<'a> VM {
     pub fn new() -> Self {
         Self {
@@ -77,7 +363,7 @@ impl<'a> VM {
     }
 
     pub fn exec(&mut self, program: &'a Program, offset: usize) -> Result<String, Error> {
-        if let None = self.root_instance {
+        if self.root_instance.is_none() {
             self.root_scope = Some(Rc::from(RefCell::from(Scope::initial(
                 Rc::clone(&self.root_pool),
                 Rc::clone(&self.root_stack)
@@ -90,8 +376,25 @@ impl<'a> VM {
         root_instance.set_variable(String::from("print"), Rc::from(Value::NativeFunction {
             function: functions::print_value
         }));
+        root_instance.set_variable(String::from("keys"), Rc::from(Value::NativeFunction {
+            function: functions::keys
+        }));
+        root_instance.set_variable(String::from("has"), Rc::from(Value::NativeFunction {
+            function: functions::has
+        }));
+
+        // A line failing halfway leaves its scopes, loops and values behind, which the next line should not see
+        let scope = Rc::clone(&root_instance.scope);
+        let stack_index = self.root_stack.borrow().stacki;
+        let result = root_instance.exec(program, offset);
+
+        if result.is_err() {
+            root_instance.scope = scope;
+            root_instance.loops.clear();
+            self.root_stack.borrow_mut().truncate(stack_index);
+        }
 
-        root_instance.exec(program, offset)
+        result
     }
 
     pub fn garbage(&mut self) {
@@ -111,7 +414,8 @@ impl<'a> VM {
 
 pub struct VMInstance {
     scope: Rc<RefCell<Scope>>,
-    instruction_count: Rc<RefCell<usize>>
+    instruction_count: Rc<RefCell<usize>>,
+    loops: Vec<i32> // Stack heights on entering each enclosing loop
 }
 
 impl std::fmt::Debug for VMInstance {
@@ -120,7 +424,7 @@ impl std::fmt::Debug for VMInstance {
     }
 }
 
-impl<'a, 'r> VMInstance {
+impl<'a> VMInstance {
     pub fn new(parent_scope: Rc<RefCell<Scope>>) -> Self {
         Self::from_instruction_count(parent_scope, Rc::from(RefCell::from(0)))
     }
@@ -128,7 +432,8 @@ impl<'a, 'r> VMInstance {
     fn from_instruction_count(parent_scope: Rc<RefCell<Scope>>, instruction_count: Rc<RefCell<usize>>) -> Self {
         Self {
             scope: Rc::from(RefCell::from(Scope::new(parent_scope))),
-            instruction_count
+            instruction_count,
+            loops: Vec::new()
         }
     }
 
@@ -152,9 +457,7 @@ impl<'a, 'r> VMInstance {
         Ok(match &**val {
             Value::Variable { identifier, .. } => {
                 self.scope.borrow_mut().get_variable(identifier)
-                    .or(Some(Rc::from(NULL)))
-                    .map(|v| v)
-                    .unwrap()
+                    .unwrap_or(Rc::from(NULL))
             },
             _ => Rc::clone(val)
         })
@@ -176,7 +479,9 @@ impl<'a, 'r> VMInstance {
         };
 
         let second = self.get_variable(&stack_second)?;
-        self.scope.borrow_mut().set_variable(String::from(identifier), second);
+        if !self.scope.borrow_mut().assign_variable(String::from(identifier), second) {
+            return Err(assign_to_constant(instruction, identifier));
+        }
 
         self.push(instruction, Rc::clone(&stack_first))?;
     
@@ -189,64 +494,134 @@ impl<'a, 'r> VMInstance {
 
         match (&*stack_first, &*stack_second) {
             (&Value::Int(first), &Value::Int(second)) => {
+                // Dividing the smallest Int by -1 overflows, while the remainder is simply 0
                 let res = match instruction.code {
-                    Code::Add => first + second,
-                    Code::Subtract => first - second,
-                    Code::Multiply => first * second,
-                    Code::Divide => first / second,
-                    _ => return Err(operation_not_supported(instruction, &*stack_first, &*stack_second))
+                    Code::Divide | Code::Modulo if second == 0 => return Err(
+                        Error::new(instruction.offset, instruction.width, ErrorType::VMError(VMErrorType::DivisionByZero))
+                            .with_description(format!("Operation [{:?}] with the Int [{}] by zero", instruction.code, first))
+                    ),
+                    Code::Add => first.checked_add(second).map(Value::Int),
+                    Code::Subtract => first.checked_sub(second).map(Value::Int),
+                    Code::Multiply => first.checked_mul(second).map(Value::Int),
+                    Code::Divide => first.checked_div(second).map(Value::Int),
+                    Code::Modulo => Some(Value::Int(first.wrapping_rem(second))),
+                    // A negative exponent gives a fraction, so the result is promoted to a Float
+                    Code::Power if second < 0 => Some(Value::Float((first as f64).powf(second as f64))),
+                    Code::Power => u32::try_from(second).ok()
+                        .and_then(|second| first.checked_pow(second))
+                        .map(Value::Int),
+                    _ => return Err(operation_not_supported(instruction, &stack_first, &stack_second))
                 };
 
-                let val = self.create(Value::Int(res));
+                let val = match res {
+                    Some(res) => self.create(res),
+                    None => return Err(integer_overflow(instruction, first, second))
+                };
                 self.push(instruction, val)?;
             },
+            // Floats follow IEEE 754, so dividing by zero gives inf or -inf, and 0.0 / 0.0 gives NaN.
+            // NaN is not equal to anything, not even itself, but is truthy since it is not 0.0
             (&Value::Float(first), &Value::Float(second)) => {
                 let res = match instruction.code {
                     Code::Add => first + second,
                     Code::Subtract => first - second,
                     Code::Multiply => first * second,
                     Code::Divide => first / second,
-                    _ => return Err(operation_not_supported(instruction, &*stack_first, &*stack_second))
+                    Code::Modulo => first % second,
+                    Code::Power => first.powf(second),
+                    _ => return Err(operation_not_supported(instruction, &stack_first, &stack_second))
                 };
 
                 let val = self.create(Value::Float(res));
                 self.push(instruction, val)?;
             },
             (&Value::Int(first), &Value::Float(second)) => {
-                let first = f64::from(first);
+                let first = first as f64;
 
                 let res = match instruction.code {
                     Code::Add => first + second,
                     Code::Subtract => first - second,
                     Code::Multiply => first * second,
                     Code::Divide => first / second,
-                    _ => return Err(operation_not_supported(instruction, &*stack_first, &*stack_second))
+                    Code::Modulo => first % second,
+                    Code::Power => first.powf(second),
+                    _ => return Err(operation_not_supported(instruction, &stack_first, &stack_second))
                 };
 
                 let val = self.create(Value::Float(res));
                 self.push(instruction, val)?;
             },
             (&Value::Float(first), &Value::Int(second)) => {
-                let second = f64::from(second);
+                let second = second as f64;
 
                 let res = match instruction.code {
                     Code::Add => first + second,
                     Code::Subtract => first - second,
                     Code::Multiply => first * second,
                     Code::Divide => first / second,
-                    _ => return Err(operation_not_supported(instruction, &*stack_first, &*stack_second))
+                    Code::Modulo => first % second,
+                    Code::Power => first.powf(second),
+                    _ => return Err(operation_not_supported(instruction, &stack_first, &stack_second))
                 };
 
                 let val = self.create(Value::Float(res));
                 self.push(instruction, val)?;
             },
-            _ => return Err(operation_not_supported(instruction, &*stack_first, &*stack_second))
+            (Value::String(first), Value::String(second)) => {
+                let res = match instruction.code {
+                    Code::Add if first.len() + second.len() > MAX_STRING_LENGTH => {
+                        return Err(string_too_large(instruction, Some(first.len() + second.len())));
+                    },
+                    Code::Add => format!("{}{}", first, second),
+                    _ => return Err(operation_not_supported(instruction, &stack_first, &stack_second))
+                };
+
+                let val = self.create(Value::String(res));
+                self.push(instruction, val)?;
+            },
+            // Repeating a string a negative amount of times gives an empty string
+            (Value::String(s), &Value::Int(times)) |
+            (&Value::Int(times), Value::String(s)) => {
+                let length = usize::try_from(times.max(0)).ok()
+                    .and_then(|times| s.len().checked_mul(times));
+
+                let res = match (&instruction.code, length) {
+                    (Code::Multiply, Some(length)) if length <= MAX_STRING_LENGTH => s.repeat(times.max(0) as usize),
+                    (Code::Multiply, length) => return Err(string_too_large(instruction, length)),
+                    _ => return Err(operation_not_supported(instruction, &stack_first, &stack_second))
+                };
+
+                let val = self.create(Value::String(res));
+                self.push(instruction, val)?;
+            },
+            _ => return Err(operation_not_supported(instruction, &stack_first, &stack_second))
         };
 
         STATUS_OK
     }
 
-    pub fn do_exec(&mut self, program: &'a Program, from: usize) -> Result<(), Error> {
+    fn compare(&mut self, instruction: &'a Instruction) -> Status {
+        let (pop_second, pop_first) = (self.pop(instruction)?, self.pop(instruction)?);
+        let (stack_second, stack_first) = (self.get_variable(&pop_second)?, self.get_variable(&pop_first)?);
+        let (first, second) = (&*stack_first, &*stack_second);
+
+        // Anything can be checked for equality, but only some types can be ordered
+        let res = match instruction.code {
+            Code::Equal => first == second,
+            Code::NotEqual => first != second,
+            Code::Less => ordering(instruction, first, second)? == Some(Ordering::Less),
+            Code::LessEqual => matches!(ordering(instruction, first, second)?, Some(Ordering::Less | Ordering::Equal)),
+            Code::Greater => ordering(instruction, first, second)? == Some(Ordering::Greater),
+            Code::GreaterEqual => matches!(ordering(instruction, first, second)?, Some(Ordering::Greater | Ordering::Equal)),
+            _ => return Err(operation_not_supported(instruction, first, second))
+        };
+
+        let val = self.create(Value::Bool(res));
+        self.push(instruction, val)
+    }
+
+    // Returns the value of a return statement, if one was executed
+    pub fn do_exec(&mut self, program: &'a Program, from: usize) -> Result<Option<Rc<Value>>, Error> {
         let mut index = from;
 
         loop {
@@ -257,6 +632,10 @@ impl<'a, 'r> VMInstance {
             let instruction = &program[index];
 
             match &instruction.code {
+                Code::PushBool(b) => {
+                    let val = self.create(Value::Bool(*b));
+                    self.push(instruction, val)?;
+                },
                 Code::PushNum(i) => {
                     let val = self.create(Value::Int(*i));
                     self.push(instruction, val)?;
@@ -275,38 +654,281 @@ impl<'a, 'r> VMInstance {
                         let val = self.pop(instruction)?;   
                         items.push(self.get_variable(&val)?);
                     }
-                    self.push(instruction, Rc::from(Value::List(items.into_iter().rev().collect())))?;
+                    self.push(instruction, Rc::from(Value::List(List::new(items.into_iter().rev().collect()))))?;
+                },
+                Code::PushMap(len) => {
+                    let mut pairs = Vec::new();
+                    for _ in 0..*len {
+                        let val = self.pop(instruction)?;
+                        let val = self.get_variable(&val)?;
+
+                        let key = self.pop(instruction)?;
+                        let key = map_key(instruction, &*self.get_variable(&key)?)?;
+
+                        pairs.push((key, val));
+                    }
+
+                    // Later keys overwrite earlier ones
+                    let items = pairs.into_iter().rev().collect();
+                    self.push(instruction, Rc::from(Value::Map(Map::new(items))))?;
+                },
+                Code::JoinLists(len) => {
+                    let mut lists = Vec::new();
+                    for _ in 0..*len {
+                        let val = self.pop(instruction)?;
+                        lists.push(self.get_variable(&val)?);
+                    }
+
+                    let mut items = Vec::new();
+                    for list in lists.iter().rev() {
+                        match &**list {
+                            Value::List(list) => items.extend(list.items().iter().cloned()),
+                            _ => return Err(invalid_cast(instruction, list, "List"))
+                        }
+                    }
+
+                    self.push(instruction, Rc::from(Value::List(List::new(items))))?;
+                },
+                Code::SpreadArguments(named) => {
+                    let mut values = Vec::new();
+                    for _ in 0..*named {
+                        values.push(self.pop(instruction)?);
+                    }
+
+                    let list = self.pop(instruction)?;
+                    let items = match &*self.get_variable(&list)? {
+                        Value::List(list) => list.items().clone(),
+                        _ => return Err(Error::new(instruction.offset, instruction.width, ErrorType::VMError(VMErrorType::InvalidArgumentCountType)))
+                    };
+
+                    let count = items.len() + values.len();
+                    for item in items.into_iter().chain(values.into_iter().rev()) {
+                        self.push(instruction, item)?;
+                    }
+
+                    let val = self.create(Value::Int(count as i64));
+                    self.push(instruction, val)?;
+                },
+                Code::Concat(len) => {
+                    let mut parts = Vec::new();
+                    for _ in 0..*len {
+                        let val = self.pop(instruction)?;
+                        parts.push(self.get_variable(&val)?.to_string());
+                    }
+
+                    let val = self.create(Value::String(parts.into_iter().rev().collect()));
+                    self.push(instruction, val)?;
                 },
                 Code::PushListIndex => {
                     let index = self.pop(instruction)?;
                     let index = self.get_variable(&index)?;
-                    let index: i32 = match &*index {
-                        Value::Int(i) => *i,
-                        _ => return Err(invalid_cast(instruction, &*index, "Int"))
-                    };
 
                     let list = self.pop(instruction)?;
                     let list = self.get_variable(&list)?;
 
                     match &*list {
                         Value::List(list) => {
-                            if index >= list.len() as i32 {
-                                return Err(Error::new(instruction.offset, instruction.width, ErrorType::VMError(VMErrorType::IndexOutOfBounds {
-                                    list_size: list.len() as i32,
-                                    index
-                                })));
+                            let index = list_index(instruction, list.len(), &index)?;
+                            self.push(instruction, Rc::clone(&list.items()[index]))?;
+                        },
+                        Value::String(s) => {
+                            let index = list_index(instruction, s.chars().count(), &index)?;
+                            let val = self.create(Value::String(s.chars().skip(index).take(1).collect()));
+                            self.push(instruction, val)?;
+                        },
+                        Value::Map(map) => {
+                            let key = map_key(instruction, &index)?;
+
+                            match map.get(&key) {
+                                Some(val) => self.push(instruction, val)?,
+                                None => return Err(Error::new(instruction.offset, instruction.width, ErrorType::VMError(VMErrorType::KeyNotFound {
+                                    key
+                                })))
                             }
+                        },
+                        _ => return Err(invalid_cast(instruction, &list, "List, Map or String"))
+                    }
+                },
+                Code::PushSlice => {
+                    let (end, start) = (self.pop(instruction)?, self.pop(instruction)?);
+                    let (end, start) = (self.get_variable(&end)?, self.get_variable(&start)?);
+
+                    let list = self.pop(instruction)?;
+                    let list = self.get_variable(&list)?;
 
-                            self.push(instruction, Rc::clone(&list[index as usize]))?;
+                    let val = match &*list {
+                        Value::List(list) => {
+                            let (start, end) = slice_range(instruction, list.len(), &start, &end)?;
+                            Value::List(List::new(list.items()[start..end].to_vec()))
+                        },
+                        Value::String(s) => {
+                            let (start, end) = slice_range(instruction, s.chars().count(), &start, &end)?;
+                            Value::String(s.chars().skip(start).take(end - start).collect())
                         },
-                        _ => return Err(invalid_cast(instruction, &*list, "List"))
+                        _ => return Err(invalid_cast(instruction, &list, "List or String"))
+                    };
+
+                    let val = self.create(val);
+                    self.push(instruction, val)?;
+                },
+                Code::AssignIndex => {
+                    let val = self.pop(instruction)?;
+                    let val = self.get_variable(&val)?;
+
+                    let index = self.pop(instruction)?;
+                    let index = self.get_variable(&index)?;
+
+                    let list = self.pop(instruction)?;
+                    let list = self.get_variable(&list)?;
+
+                    match &*list {
+                        Value::List(list) => {
+                            let index = list_index(instruction, list.len(), &index)?;
+                            list.items_mut()[index] = Rc::clone(&val);
+                        },
+                        Value::Map(map) => {
+                            let key = map_key(instruction, &index)?;
+                            map.items_mut().insert(key, Rc::clone(&val));
+                        },
+                        _ => return Err(invalid_cast(instruction, &list, "List or Map"))
                     }
-                }
+
+                    self.push(instruction, val)?;
+                },
+                Code::PushMember(ref name) => {
+                    let object = self.pop(instruction)?;
+                    let object = self.get_variable(&object)?;
+
+                    // Fields of a map take precedence over its methods
+                    let field = match &*object {
+                        Value::Map(map) => map.get(name),
+                        _ => None
+                    };
+
+                    let val = match (field, methods::find(&object, name)) {
+                        (Some(field), _) => field,
+                        (None, Some(method)) => self.create(Value::Method { receiver: Rc::clone(&object), method }),
+                        (None, None) => return Err(
+                            Error::new(instruction.offset, instruction.width, ErrorType::VMError(VMErrorType::UnknownMember {
+                                member: String::from(name)
+                            }))
+                                .with_description(format!("[{}] has no member [{}]", object.type_name(), name))
+                        )
+                    };
+
+                    self.push(instruction, val)?;
+                },
+                Code::Duplicate(amount) => {
+                    let mut values = Vec::new();
+                    for _ in 0..*amount {
+                        values.push(self.pop(instruction)?);
+                    }
+
+                    for _ in 0..2 {
+                        for val in values.iter().rev() {
+                            self.push(instruction, Rc::clone(val))?;
+                        }
+                    }
+                },
+
+                Code::PushRange => {
+                    let (end, start) = (self.pop(instruction)?, self.pop(instruction)?);
+                    let (end, start) = (self.get_variable(&end)?, self.get_variable(&start)?);
+
+                    let val = match (&*start, &*end) {
+                        (Value::Int(start), Value::Int(end)) => Value::Range { start: *start, end: *end },
+                        (Value::Int(_), _) => return Err(invalid_cast(instruction, &end, "Int")),
+                        _ => return Err(invalid_cast(instruction, &start, "Int"))
+                    };
+
+                    let val = self.create(val);
+                    self.push(instruction, val)?;
+                },
+                Code::PushIterator => {
+                    let iterable = self.pop(instruction)?;
+                    let iterable = self.get_variable(&iterable)?;
+
+                    match &*iterable {
+                        Value::List(_) | Value::Range { .. } => {},
+                        _ => return Err(invalid_cast(instruction, &iterable, "Iterable"))
+                    };
+
+                    let val = self.create(Value::Iterator { iterable, index: 0 });
+                    self.push(instruction, val)?;
+                },
+                Code::IterNext(offset) => {
+                    let iterator = self.pop(instruction)?;
+                    let (iterable, position) = match &*iterator {
+                        Value::Iterator { iterable, index } => (iterable, *index),
+                        _ => return Err(invalid_cast(instruction, &iterator, "Iterator"))
+                    };
+
+                    let next = match &**iterable {
+                        Value::List(list) => list.get(position),
+                        Value::Range { start, end } => {
+                            let i = *start + position as i64;
+                            if i < *end { Some(self.create(Value::Int(i))) } else { None }
+                        },
+                        _ => return Err(invalid_cast(instruction, iterable, "Iterable"))
+                    };
+
+                    match next {
+                        Some(next) => {
+                            let val = self.create(Value::Iterator { iterable: Rc::clone(iterable), index: position + 1 });
+                            self.push(instruction, val)?;
+                            self.push(instruction, next)?;
+                        },
+                        None => {
+                            self.push(instruction, Rc::clone(&iterator))?;
+                            index = (index as i32 + offset) as usize;
+                        }
+                    }
+                },
 
                 Code::Add |
                 Code::Subtract |
                 Code::Multiply |
-                Code::Divide => self.compute_two_operands(instruction)?,
+                Code::Divide |
+                Code::Modulo |
+                Code::Power => self.compute_two_operands(instruction)?,
+
+                Code::Equal |
+                Code::NotEqual |
+                Code::Less |
+                Code::LessEqual |
+                Code::Greater |
+                Code::GreaterEqual => self.compare(instruction)?,
+
+                Code::Negate => {
+                    let val = self.pop(instruction)?;
+                    let val = self.get_variable(&val)?;
+
+                    let res = match &*val {
+                        Value::Int(i) => match i.checked_neg() {
+                            Some(i) => Value::Int(i),
+                            None => return Err(
+                                Error::new(instruction.offset, instruction.width, ErrorType::VMError(VMErrorType::IntegerOverflow))
+                                    .with_description(format!("Operation [{:?}] overflowed for operand [{}]", instruction.code, i))
+                            )
+                        },
+                        Value::Float(f) => Value::Float(-f),
+                        _ => return Err(
+                            Error::new(instruction.offset, instruction.width, ErrorType::VMError(VMErrorType::OperationNotSupported))
+                                .with_description(format!("Operation [{:?}] not supported for operand of type [{:?}]", instruction.code, val))
+                        )
+                    };
+
+                    let val = self.create(res);
+                    self.push(instruction, val)?;
+                },
+                Code::Not |
+                Code::ToBool => {
+                    let val = self.pop(instruction)?;
+                    let truthy = self.get_variable(&val)?.is_truthy();
+
+                    let val = self.create(Value::Bool(if let Code::Not = instruction.code { !truthy } else { truthy }));
+                    self.push(instruction, val)?;
+                },
 
                 Code::Assign => self.assign(instruction)?,
                 Code::PushVar(ref identifier) => {
@@ -315,18 +937,28 @@ impl<'a, 'r> VMInstance {
                     });
                     self.push(instruction, val)?;
                 },
+                Code::Deref => {
+                    let val = self.pop(instruction)?;
+                    let val = self.get_variable(&val)?;
+                    self.push(instruction, val)?;
+                },
 
                 Code::PushFunction { body_len, .. } => {
                     let val = self.create(Value::Function {
-                        position: index
+                        position: index,
+                        scope: Rc::clone(&self.scope)
                     });
                     self.push(instruction, val)?;
                     index += body_len; // Jump past the function body
                 },
 
-                Code::CallFunction { args_len } => {
+                Code::CallFunction { args_len, named } => {
                     let mut instance = self.instance();
-                    instance.do_exec(program, index + 1)?;
+
+                    // Returning while evaluating the arguments returns from the enclosing function
+                    if let Some(val) = instance.do_exec(program, index + 1)? {
+                        return Ok(Some(val));
+                    }
 
                     let arg_count: usize = match &*instance.pop(instruction)? {
                         Value::Int(i) => *i as usize,
@@ -338,29 +970,37 @@ impl<'a, 'r> VMInstance {
                         let var = &instance.pop(instruction)?;
                         args.push(instance.get_variable(var)?);
                     }
+                    args.reverse();
 
-                    let func = &self.pop(&instruction)?;
+                    let func = &self.pop(instruction)?;
                     let func = &*self.get_variable(func)?;
 
+                    // Only functions of the language have named parameters
+                    if let (Some(name), Value::NativeFunction { .. }) | (Some(name), Value::Method { .. }) = (named.first(), func) {
+                        return Err(Error::new(instruction.offset, instruction.width, ErrorType::VMError(VMErrorType::UnknownParameter {
+                            parameter: name.clone()
+                        })).with_description(String::from("Built-in functions do not take named arguments")));
+                    }
+
                     match func {
-                        Value::Function { position } => match &program[*position].code {
-                            Code::PushFunction { pars, .. } => {
-                                if pars.len() != args.len() {
-                                    return Err(Error::new(instruction.offset, instruction.width, ErrorType::VMError(VMErrorType::MismatchedArgumentCount)));
-                                }
+                        Value::Function { position, scope } => match &program[*position].code {
+                            Code::PushFunction { pars, rest, .. } => {
+                                let bindings = bind_arguments(instruction, pars, rest, named, args)?;
+
+                                // The body runs in the scope the function was defined in, not the one it is called from
+                                let mut instance = VMInstance::from_instruction_count(Rc::clone(scope), Rc::clone(&self.instruction_count));
 
-                                for i in 0..pars.len() {
-                                    instance.set_variable(pars[i].clone(), Rc::clone(&args[pars.len() - 1 - i]));
+                                for (name, val) in bindings {
+                                    instance.set_variable(name, val);
                                 }
 
                                 let stack_index = instance.scope.borrow().stack.borrow().stacki;
-                                instance.do_exec(program, *position + 1)?;
+                                let val = instance.do_exec(program, *position + 1)?
+                                    .unwrap_or_else(|| Rc::from(NULL));
 
-                                if instance.scope.borrow().stack.borrow().stacki <= stack_index {
-                                    self.push(instruction, Rc::from(NULL))?;
-                                } else if let Some(val) = instance.pop(instruction).ok() {
-                                    self.push(instruction, instance.get_variable(&val)?)?;
-                                }
+                                // Discard whatever the function left behind, like iterators of loops it returned from
+                                instance.scope.borrow().stack.borrow_mut().truncate(stack_index);
+                                self.push(instruction, val)?;
                             },
                             _ => {
                                 // println!("{:?}", program[*position].code);
@@ -368,7 +1008,10 @@ impl<'a, 'r> VMInstance {
                             }
                         },
                         Value::NativeFunction { function } => {
-                            self.push(instruction, function(Rc::from(RefCell::from(instance)), args.into_iter().rev().collect())?)?;
+                            self.push(instruction, function(Rc::from(RefCell::from(instance)), instruction, args)?)?;
+                        },
+                        Value::Method { receiver, method } => {
+                            self.push(instruction, method(Rc::from(RefCell::from(instance)), instruction, Rc::clone(receiver), args)?)?;
                         }
                         _ => {
                             // println!("{:?}: {:?}", func, &*self.get_variable(func)?);
@@ -380,9 +1023,100 @@ impl<'a, 'r> VMInstance {
                     index += args_len; // Jump past the arguments
                 }
 
+                Code::Declare(ref identifier) => {
+                    let val = self.pop(instruction)?;
+                    let val = self.get_variable(&val)?;
+                    self.set_variable(String::from(identifier), val);
+                },
+                Code::DeclareConstant(ref identifier) => {
+                    let val = self.pop(instruction)?;
+                    let val = self.get_variable(&val)?;
+                    self.scope.borrow_mut().set_constant(String::from(identifier), val);
+                },
+                Code::AssignVar(ref identifier) => {
+                    let val = self.pop(instruction)?;
+                    let val = self.get_variable(&val)?;
+                    if !self.scope.borrow_mut().assign_variable(String::from(identifier), val) {
+                        return Err(assign_to_constant(instruction, identifier));
+                    }
+                },
+                Code::Unpack { count, rest } => {
+                    let list = self.pop(instruction)?;
+                    let list = self.get_variable(&list)?;
+
+                    let items = match &*list {
+                        Value::List(list) => list.items().clone(),
+                        _ => return Err(invalid_cast(instruction, &list, "List"))
+                    };
+
+                    if items.len() < *count || (!rest && items.len() != *count) {
+                        return Err(
+                            Error::new(instruction.offset, instruction.width, ErrorType::VMError(VMErrorType::MismatchedPatternLength {
+                                expected: *count,
+                                found: items.len()
+                            }))
+                                .with_description(format!(
+                                    "Expected {}{} items to destructure, found {}",
+                                    if *rest { "at least " } else { "" }, count, items.len()
+                                ))
+                        );
+                    }
+
+                    if *rest {
+                        let val = self.create(Value::List(List::new(items[*count..].to_vec())));
+                        self.push(instruction, val)?;
+                    }
+
+                    for item in items[..*count].iter().rev() {
+                        self.push(instruction, Rc::clone(item))?;
+                    }
+                },
+                Code::PushScope => {
+                    self.scope = Rc::from(RefCell::from(Scope::block(Rc::clone(&self.scope))));
+                },
+                Code::PopScope => {
+                    let parent = match self.scope.borrow().parent {
+                        Some(ref parent) => Rc::clone(parent),
+                        None => return Err(unimplemented(instruction.offset, instruction.width)
+                            .with_description(String::from("Can not leave the root scope")))
+                    };
+
+                    self.scope = parent;
+                },
+
+                Code::EnterLoop => {
+                    let stack_index = self.scope.borrow().stack.borrow().stacki;
+                    self.loops.push(stack_index);
+                },
+                Code::ExitLoop => { self.loops.pop(); },
+                Code::RestoreStack => {
+                    if let Some(stack_index) = self.loops.last() {
+                        self.scope.borrow().stack.borrow_mut().truncate(*stack_index);
+                    }
+                },
+                Code::JumpIfDeclared(ref identifier, offset) => {
+                    if self.scope.borrow().variables.contains_key(identifier) {
+                        index = (index as i32 + offset) as usize;
+                    }
+                },
+                Code::Jump(offset) => {
+                    index = (index as i32 + offset) as usize;
+                },
+                Code::JumpIfFalse(offset) => {
+                    let condition = self.pop(instruction)?;
+
+                    if !self.get_variable(&condition)?.is_truthy() {
+                        index = (index as i32 + offset) as usize;
+                    }
+                },
+
                 Code::PushNull => { self.push(instruction, Rc::from(NULL))? },
                 Code::Pop => { self.pop(instruction)?; },
-                Code::Return => { break; },
+                Code::EndArguments => { break; },
+                Code::Return => {
+                    let val = self.pop(instruction)?;
+                    return Ok(Some(self.get_variable(&val)?));
+                },
 
                 _ => return Err(
                     unimplemented(instruction.offset, instruction.width)
@@ -399,7 +1133,7 @@ impl<'a, 'r> VMInstance {
             }
         }
 
-        Ok(())
+        Ok(None)
     }
 
     pub fn garbage(&mut self) {
@@ -426,7 +1160,7 @@ impl<'a, 'r> VMInstance {
             "{:?}",
             self.pop(&Instruction::new(0, 0, Code::Null))
                 .as_ref()
-                .map(|v| self.get_variable(&v))
+                .map(|v| self.get_variable(v))
                 .unwrap_or(Ok(Rc::from(NULL)))?
         ))
     }
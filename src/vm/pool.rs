@@ -23,7 +23,7 @@ impl Pool {
     pub fn garbage(&mut self) {
         self.pool = self.pool.iter()
             .filter(|v| Rc::strong_count(v) > 1)
-            .map(|v| Rc::clone(v))
+            .map(Rc::clone)
             .collect::<Vec<Rc<Value>>>();
     }
 }
\ No newline at end of file
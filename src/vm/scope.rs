@@ -1,32 +1,52 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 use std::cell::RefCell;
+use std::fmt;
 
 use super::{Pool, Stack, Value};
 
 pub struct Scope {
     pub parent: Option<Rc<RefCell<Scope>>>,
+    pub block: bool, // Assignments pass through block scopes to the enclosing ones
     pub variables: HashMap<String, Rc<Value>>,
+    pub constants: HashSet<String>, // Names declared with const, which can not be assigned to
     pub pool: Rc<RefCell<Pool>>,
     pub stack: Rc<RefCell<Stack>>,
 }
 
+impl fmt::Debug for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Scope at {:p}", self)
+    }
+}
+
 impl Scope {
     pub fn new(parent: Rc<RefCell<Scope>>) -> Self {
         let p = parent.borrow_mut();
 
         Scope {
             parent: Some(Rc::clone(&parent)),
+            block: false,
             variables: HashMap::new(),
+            constants: HashSet::new(),
             pool: Rc::clone(&p.pool),
             stack: Rc::clone(&p.stack),
         }
     }
 
+    pub fn block(parent: Rc<RefCell<Scope>>) -> Self {
+        Scope {
+            block: true,
+            ..Scope::new(parent)
+        }
+    }
+
     pub fn initial(pool: Rc<RefCell<Pool>>, stack: Rc<RefCell<Stack>>) -> Self {
         Scope {
             parent: None,
+            block: false,
             variables: HashMap::new(),
+            constants: HashSet::new(),
             pool,
             stack,
         }
@@ -34,14 +54,41 @@ impl Scope {
 
     pub fn get_variable(&self, identifier: &str) -> Option<Rc<Value>> {
         self.variables.get(identifier)
-            .map(|v| Rc::clone(v))
+            .map(Rc::clone)
             .or_else(|| self.parent.as_ref()
                 .and_then(|parent| parent.borrow()
                     .get_variable(identifier)))
     }
 
     pub fn set_variable(&mut self, identifier: String, value: Rc<Value>) {
+        self.constants.remove(&identifier);
+        self.variables.insert(identifier, value);
+    }
+
+    pub fn set_constant(&mut self, identifier: String, value: Rc<Value>) {
+        self.constants.insert(identifier.clone());
+        self.variables.insert(identifier, value);
+    }
+
+    // Overwrites the variable in the scope it was defined in, otherwise sets it in the nearest non-block scope.
+    // Returns false without assigning if the variable is a constant
+    pub fn assign_variable(&mut self, identifier: String, value: Rc<Value>) -> bool {
+        if !self.variables.contains_key(&identifier) {
+            if let Some(parent) = &self.parent {
+                let defined = parent.borrow().get_variable(&identifier).is_some();
+
+                if defined || self.block {
+                    return parent.borrow_mut().assign_variable(identifier, value);
+                }
+            }
+        }
+
+        if self.constants.contains(&identifier) {
+            return false;
+        }
+
         self.variables.insert(identifier, value);
+        true
     }
 
     pub fn garbage(&mut self) {
@@ -22,7 +22,7 @@ impl<'a> Stack {
         if index < 0 || index >= STACK_SIZE as i32 {
             Err(
                 Error::new(instruction.offset, instruction.width, ErrorType::VMError(VMErrorType::StackOverflow {
-                    stack_size: STACK_SIZE as usize,
+                    stack_size: STACK_SIZE,
                     index
                 })).with_description(format!("Stack overflow during operation [{:?}]", instruction.code))
             )
@@ -40,6 +40,14 @@ impl<'a> Stack {
         STATUS_OK
     }
 
+    // Drops everything above the given stack index
+    pub fn truncate(&mut self, stacki: i32) {
+        while self.stacki > stacki {
+            self.stack[self.stacki as usize] = None;
+            self.stacki -= 1;
+        }
+    }
+
     pub fn pop(&mut self, instruction: &'a Instruction) -> Result<Rc<Value>, Error> {
         self.check_range(instruction, -1)?;
 
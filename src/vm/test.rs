@@ -0,0 +1,986 @@
+use super::*;
+use super::super::error::{CompilerErrorType, LexerErrorType, ParserErrorType, ResolverErrorType};
+use super::super::lexer::Lexer;
+use super::super::parser::Parser;
+use super::super::resolver::Resolver;
+use super::super::compiler::Compiler;
+
+fn run_resolved(code: &str, mut resolver: Resolver) -> Result<String, Error> {
+    let lexed = Lexer::new().lex(String::from(code), 0)?;
+    let parsed = Parser::new().parse(&lexed)?;
+    resolver.resolve(&parsed)?;
+    let compiled = Compiler::new().compile(&parsed)?;
+
+    VM::new().exec(&compiled, 0)
+}
+
+fn run(code: &str) -> Result<String, Error> {
+    run_resolved(code, Resolver::new())
+}
+
+fn run_strict(code: &str) -> Result<String, Error> {
+    let mut resolver = Resolver::new();
+    resolver.set_strict(true);
+
+    run_resolved(code, resolver)
+}
+
+// Runs each line on the same VM, like the shell does
+fn run_lines(lines: &[&str]) -> Vec<Result<String, Error>> {
+    let mut resolver = Resolver::new();
+    let mut vm = VM::new();
+    let mut program = Vec::new();
+    let mut code_offset = 0;
+
+    lines.iter().map(|line| -> Result<String, Error> {
+        let lexed = Lexer::new().lex(String::from(*line), code_offset)?;
+        let parsed = Parser::new().parse(&lexed)?;
+        resolver.resolve(&parsed)?;
+        let mut compiled = Compiler::new().compile(&parsed)?;
+        code_offset += line.len();
+
+        let offset = program.len();
+        program.append(&mut compiled);
+        let executed = vm.exec(&program, offset)?;
+        resolver.commit();
+
+        Ok(executed)
+    }).collect()
+}
+
+#[test]
+fn if_takes_branch() {
+    assert_eq!(run("if 1 { 2 } else { 3 }").unwrap(), "Int(2)");
+    assert_eq!(run("if 0 { 2 } else { 3 }").unwrap(), "Int(3)");
+    assert_eq!(run("if null { 2 }").unwrap(), "Null");
+}
+
+#[test]
+fn else_if_chain() {
+    let code = "f = x => if x - 1 { if x - 2 { 3 } else { 2 } } else if x { 1 } else { 0 };";
+
+    assert_eq!(run(&format!("{} f(1)", code)).unwrap(), "Int(1)");
+    assert_eq!(run(&format!("{} f(2)", code)).unwrap(), "Int(2)");
+    assert_eq!(run(&format!("{} f(3)", code)).unwrap(), "Int(3)");
+}
+
+#[test]
+fn if_as_statement() {
+    assert_eq!(run("a = 1; if a { a = 5; } a").unwrap(), "Int(5)");
+    assert_eq!(run("a = 1; b = if a { 10 } + 1; b").unwrap(), "Int(11)");
+}
+
+#[test]
+fn while_loops() {
+    assert_eq!(run("n = 5; sum = 0; while n { sum = sum + n; n = n - 1; } sum").unwrap(), "Int(15)");
+    assert_eq!(run("n = 0; while n { n = 1; }").unwrap(), "Null");
+}
+
+#[test]
+fn break_and_continue() {
+    assert_eq!(run("n = 0; while 1 { n = n + 1; if n - 3 { continue; } break; } n").unwrap(), "Int(3)");
+
+    let code = "
+        i = 3; count = 0;
+        while i {
+            i = i - 1;
+            j = 3;
+            while 1 {
+                j = j - 1;
+                if j { continue; }
+                break;
+            }
+            count = count + 1;
+        }
+        count
+    ";
+    assert_eq!(run(code).unwrap(), "Int(3)");
+}
+
+#[test]
+fn long_loops_do_not_overflow_the_stack() {
+    assert_eq!(run("n = 1000; while n { n - 1; n = n - 1 } n").unwrap(), "Int(0)");
+}
+
+#[test]
+fn break_in_the_middle_of_an_expression() {
+    assert_eq!(run("n = 0; while n < 600 { n += 1; y = [n, if true { continue; } else { 0 }]; } n").unwrap(), "Int(600)");
+    assert_eq!(run("n = 0; while n < 600 { n += 1; while true { y = 1 + if true { break; } else { 0 }; } } n").unwrap(), "Int(600)");
+    assert_eq!(run("n = 0; while n < 600 { n += 1; for x in [1] { y = 1 + if true { break; } else { 0 }; } } n").unwrap(), "Int(600)");
+    assert_eq!(run("sum = 0; for x in 0..600 { sum += [x, if x % 2 { continue; } else { x }][1]; } sum").unwrap(), "Int(89700)");
+}
+
+#[test]
+fn break_outside_loop() {
+    assert_matches!(
+        run("break;"),
+        Err(Error { error_type: ErrorType::CompilerError(CompilerErrorType::BreakOutsideLoop), .. })
+    );
+    assert_matches!(
+        run("while 1 { f = () => { continue; }; break; }"),
+        Err(Error { error_type: ErrorType::CompilerError(CompilerErrorType::ContinueOutsideLoop), .. })
+    );
+    assert_matches!(
+        run("f = (a) => a; i = 0; while true { i += 1; f(if i == 3 { break; } else { i }); } i"),
+        Err(Error { error_type: ErrorType::CompilerError(CompilerErrorType::BreakOutsideLoop), offset: 56, width: 5, .. })
+    );
+}
+
+#[test]
+fn for_loops() {
+    assert_eq!(run("sum = 0; for x in [1, 2, 3] { sum = sum + x; } sum").unwrap(), "Int(6)");
+    assert_eq!(run("sum = 0; for i in 0..5 { sum = sum + i } sum").unwrap(), "Int(10)");
+    assert_eq!(run("n = 3; sum = 0; for i in n..n + 2 { sum = sum + i } sum").unwrap(), "Int(7)");
+    assert_eq!(run("for i in 5..0 { i }").unwrap(), "Null");
+}
+
+#[test]
+fn failed_lines_leave_nothing_behind() {
+    let results = run_lines(&[
+        "for i in [1] { 1 / 0 }",
+        "i",
+        "{ let x = 1; x / 0 }",
+        "x",
+        "while true { [1, 2 / 0] }",
+        "y = 2;",
+        "for i in [1, 2] { i }",
+        "y"
+    ]);
+
+    assert_matches!(results[0], Err(Error { error_type: ErrorType::VMError(VMErrorType::DivisionByZero), .. }));
+    assert_eq!(results[1].as_ref().unwrap(), "Null");
+    assert_matches!(results[2], Err(Error { error_type: ErrorType::VMError(VMErrorType::DivisionByZero), .. }));
+    assert_eq!(results[3].as_ref().unwrap(), "Null");
+    assert_matches!(results[4], Err(Error { error_type: ErrorType::VMError(VMErrorType::DivisionByZero), .. }));
+    assert_eq!(results[5].as_ref().unwrap(), "Null");
+    assert_eq!(results[6].as_ref().unwrap(), "Null");
+    assert_eq!(results[7].as_ref().unwrap(), "Int(2)");
+}
+
+#[test]
+fn for_loop_variable_is_scoped() {
+    assert_eq!(run("for x in [1] { y = x; } x").unwrap(), "Null");
+    assert_eq!(run("for x in [1] { y = x; } y").unwrap(), "Int(1)");
+}
+
+#[test]
+fn for_break_and_continue() {
+    let code = "
+        sum = 0;
+        for i in 0..100 {
+            if i - 10 { } else { break; }
+            if i - 2 { continue; }
+            sum = sum + 100;
+        }
+        sum
+    ";
+    assert_eq!(run(code).unwrap(), "Int(100)");
+
+    let code = "
+        count = 0;
+        for i in 0..3 {
+            for j in [1, 2, 3, 4] {
+                if j - 3 { } else { break; }
+                count = count + 1;
+            }
+        }
+        count
+    ";
+    assert_eq!(run(code).unwrap(), "Int(6)");
+}
+
+#[test]
+fn for_over_non_iterable() {
+    assert_matches!(
+        run("for x in 5 { }"),
+        Err(Error { error_type: ErrorType::VMError(VMErrorType::InvalidCast), .. })
+    );
+}
+
+#[test]
+fn comparisons() {
+    assert_eq!(run("1 < 2").unwrap(), "Bool(true)");
+    assert_eq!(run("2 <= 2.0").unwrap(), "Bool(true)");
+    assert_eq!(run("2.5 > 3").unwrap(), "Bool(false)");
+    assert_eq!(run("3 >= 3").unwrap(), "Bool(true)");
+    assert_eq!(run("1 + 1 == 2").unwrap(), "Bool(true)");
+    assert_eq!(run("1 == 1.0").unwrap(), "Bool(true)");
+    assert_eq!(run("1 != 2 == 1 < 2").unwrap(), "Bool(true)");
+}
+
+#[test]
+fn compares_strings_and_lists() {
+    assert_eq!(run("\"abc\" < \"abd\"").unwrap(), "Bool(true)");
+    assert_eq!(run("\"b\" > \"abc\"").unwrap(), "Bool(true)");
+    assert_eq!(run("[1, [2, 3]] == [1, [2, 3.0]]").unwrap(), "Bool(true)");
+    assert_eq!(run("[1, 2] < [1, 2, 0]").unwrap(), "Bool(true)");
+    assert_eq!(run("[1, 3] > [1, 2, 5]").unwrap(), "Bool(true)");
+    assert_eq!(run("\"1\" == 1").unwrap(), "Bool(false)");
+    assert_eq!(run("null == null").unwrap(), "Bool(true)");
+}
+
+#[test]
+fn ordering_unsupported_types() {
+    assert_matches!(
+        run("\"1\" < 1"),
+        Err(Error { error_type: ErrorType::VMError(VMErrorType::OperationNotSupported), .. })
+    );
+    assert_matches!(
+        run("[1, \"a\"] < [1, 2]"),
+        Err(Error { error_type: ErrorType::VMError(VMErrorType::OperationNotSupported), .. })
+    );
+    assert_matches!(
+        run("[1, \"a\"] >= [1, 2]"),
+        Err(Error { error_type: ErrorType::VMError(VMErrorType::OperationNotSupported), .. })
+    );
+    assert_eq!(run("[1, \"a\"] < [2, 2]").unwrap(), "Bool(true)");
+    assert_eq!(run("nan = 0.0 / 0.0; [[nan] < [1], [nan] >= [1]]").unwrap(), "List([Bool(false), Bool(false)])");
+}
+
+#[test]
+fn comparisons_in_loops() {
+    assert_eq!(run("i = 0; while i < 10 { i = i + 1 } i").unwrap(), "Int(10)");
+}
+
+#[test]
+fn boolean_literals() {
+    assert_eq!(run("true").unwrap(), "Bool(true)");
+    assert_eq!(run("trueish = 1; trueish").unwrap(), "Int(1)");
+    assert_eq!(run("if false { 1 } else { 2 }").unwrap(), "Int(2)");
+}
+
+#[test]
+fn logical_operators() {
+    assert_eq!(run("true && false").unwrap(), "Bool(false)");
+    assert_eq!(run("false || 1").unwrap(), "Bool(true)");
+    assert_eq!(run("!true").unwrap(), "Bool(false)");
+    assert_eq!(run("!!5").unwrap(), "Bool(true)");
+    assert_eq!(run("1 < 2 && 2 < 3 || false").unwrap(), "Bool(true)");
+    assert_eq!(run("false && true || true").unwrap(), "Bool(true)");
+    assert_eq!(run("!(1 == 2) && !null").unwrap(), "Bool(true)");
+}
+
+#[test]
+fn logical_operators_short_circuit() {
+    assert_eq!(run("x = 0; false && (x = 1); x").unwrap(), "Int(0)");
+    assert_eq!(run("x = 0; true || (x = 1); x").unwrap(), "Int(0)");
+    assert_eq!(run("x = 0; true && (x = 1); x").unwrap(), "Int(1)");
+    assert_eq!(run("x = 0; false || (x = 1); x").unwrap(), "Int(1)");
+}
+
+#[test]
+fn unary_minus() {
+    assert_eq!(run("-5").unwrap(), "Int(-5)");
+    assert_eq!(run("-2.5").unwrap(), "Float(-2.5)");
+    assert_eq!(run("x = 3; -x").unwrap(), "Int(-3)");
+    assert_eq!(run("- -2").unwrap(), "Int(2)");
+    assert_eq!(run("2 - -3").unwrap(), "Int(5)");
+    assert_eq!(run("-2 * 3 + 1").unwrap(), "Int(-5)");
+    assert_eq!(run("xs = [1, 2]; -xs[1]").unwrap(), "Int(-2)");
+}
+
+#[test]
+fn unary_minus_error_points_at_operator() {
+    assert_matches!(
+        run("1 + -\"a\""),
+        Err(Error { error_type: ErrorType::VMError(VMErrorType::OperationNotSupported), offset: 4, width: 1, .. })
+    );
+}
+
+#[test]
+fn exponent_and_modulo() {
+    assert_eq!(run("2 ** 10").unwrap(), "Int(1024)");
+    assert_eq!(run("2 ** 3 ** 2").unwrap(), "Int(512)");
+    assert_eq!(run("2 * 3 ** 2").unwrap(), "Int(18)");
+    assert_eq!(run("-2 ** 2").unwrap(), "Int(-4)");
+    assert_eq!(run("2 ** -1").unwrap(), "Float(0.5)");
+    assert_eq!(run("4 ** 0.5").unwrap(), "Float(2.0)");
+    assert_eq!(run("7 % 3").unwrap(), "Int(1)");
+    assert_eq!(run("-7 % 3").unwrap(), "Int(-1)");
+    assert_eq!(run("7.5 % 2").unwrap(), "Float(1.5)");
+    assert_eq!(run("2 * 7 % 4").unwrap(), "Int(2)");
+}
+
+#[test]
+fn compound_assignment() {
+    assert_eq!(run("x = 5; x += 3; x").unwrap(), "Int(8)");
+    assert_eq!(run("x = 5; x -= 3; x").unwrap(), "Int(2)");
+    assert_eq!(run("x = 5; x *= 3; x").unwrap(), "Int(15)");
+    assert_eq!(run("x = 6; x /= 4.0; x").unwrap(), "Float(1.5)");
+    assert_eq!(run("x = 7; x %= 4; x").unwrap(), "Int(3)");
+    assert_eq!(run("x = 2; x **= 5; x").unwrap(), "Int(32)");
+    assert_eq!(run("sum = 0; for i in 0..4 { sum += i } sum").unwrap(), "Int(6)");
+}
+
+#[test]
+fn compound_assignment_on_list_elements() {
+    assert_eq!(run("xs = [1, 2, 3]; xs[1] += 10; xs").unwrap(), "List([Int(1), Int(12), Int(3)])");
+    assert_eq!(run("xs = [1, [2, 3]]; ys = xs; ys[1][0] *= 5; xs[1]").unwrap(), "List([Int(10), Int(3)])");
+}
+
+#[test]
+fn compound_assignment_evaluates_target_once() {
+    let code = "
+        calls = [0];
+        xs = [1, 2];
+        f = () => { calls[0] += 1; xs };
+        f()[1] += 5;
+        [calls[0], xs[1]]
+    ";
+    assert_eq!(run(code).unwrap(), "List([Int(1), Int(7)])");
+}
+
+#[test]
+fn compound_assignment_to_non_variable() {
+    assert_matches!(
+        run("5 += 1"),
+        Err(Error { error_type: ErrorType::VMError(VMErrorType::AssignToNonVariable), .. })
+    );
+}
+
+#[test]
+fn assign_to_list_elements() {
+    assert_eq!(run("a = [1, 2, 3]; a[2] = 7; a").unwrap(), "List([Int(1), Int(2), Int(7)])");
+    assert_eq!(run("a = [1, 2, 3]; a[0] = 7").unwrap(), "Int(7)");
+    assert_eq!(run("a = [1, [2, 3]]; x = 9; a[1][0] = x; a").unwrap(), "List([Int(1), List([Int(9), Int(3)])])");
+    assert_eq!(run("a = [0, 0]; for i in 0..2 { a[i] = i * 10 } a").unwrap(), "List([Int(0), Int(10)])");
+}
+
+#[test]
+fn assigned_lists_are_shared() {
+    assert_eq!(run("a = [1]; b = a; b[0] = 2; a").unwrap(), "List([Int(2)])");
+    assert_eq!(run("f = xs => { xs[0] = null; }; a = [1]; f(a); a").unwrap(), "List([Null])");
+}
+
+#[test]
+fn lists_containing_themselves() {
+    assert_eq!(run("xs = [1, 2]; xs[0] = xs; xs").unwrap(), "List([List([...]), Int(2)])");
+    assert_eq!(run("xs = [1, 2]; xs[0] = xs; \"${xs}\"").unwrap(), "String(\"[[...], 2]\")");
+    assert_eq!(run("xs = [1, 2]; xs[0] = xs; xs == [xs]").unwrap(), "Bool(false)");
+    assert_eq!(run("xs = [1, 2]; xs[0] = xs; ys = [1, 2]; ys[0] = ys; [xs == ys, xs < ys, xs >= ys]").unwrap(), "List([Bool(false), Bool(false), Bool(false)])");
+    assert_eq!(run("m = {a: 1}; m[\"a\"] = m; \"${m}\"").unwrap(), "String(\"{\\\"a\\\": {...}}\")");
+    assert_eq!(run("m = {a: 1}; m[\"a\"] = m; m == {a: m}").unwrap(), "Bool(false)");
+}
+
+#[test]
+fn assign_to_list_element_out_of_bounds() {
+    assert_matches!(
+        run("a = [1]; a[1] = 2"),
+        Err(Error { error_type: ErrorType::VMError(VMErrorType::IndexOutOfBounds { list_size: 1, index: 1 }), .. })
+    );
+    assert_matches!(
+        run("a = 5; a[0] = 2"),
+        Err(Error { error_type: ErrorType::VMError(VMErrorType::InvalidCast), .. })
+    );
+}
+
+#[test]
+fn closures_capture_their_environment() {
+    assert_eq!(run("add = a => b => a + b; add5 = add(5); add5(3)").unwrap(), "Int(8)");
+    assert_eq!(run("add = a => b => c => a + b + c; add(1)(2)(3)").unwrap(), "Int(6)");
+}
+
+#[test]
+fn closures_share_captured_variables() {
+    let code = "
+        counter = () => {
+            count = 0;
+            () => {
+                count += 1;
+                count
+            }
+        };
+        a = counter();
+        b = counter();
+        a(); a(); b();
+        [a(), b()]
+    ";
+    assert_eq!(run(code).unwrap(), "List([Int(3), Int(2)])");
+}
+
+#[test]
+fn functions_do_not_see_the_callers_scope() {
+    assert_eq!(run("f = () => y; g = y => f(); g(5)").unwrap(), "Null");
+}
+
+#[test]
+fn closures_capture_loop_variables() {
+    let code = "
+        fs = [];
+        for i in 0..3 { fs = [fs, () => i * 10]; }
+        f = fs[0][1];
+        f()
+    ";
+    assert_eq!(run(code).unwrap(), "Int(10)");
+}
+
+#[test]
+fn recursion() {
+    assert_eq!(run("fact = n => if n <= 1 { 1 } else { n * fact(n - 1) }; fact(10)").unwrap(), "Int(3628800)");
+}
+
+#[test]
+fn return_statement() {
+    assert_eq!(run("f = x => { return x * 2; 5 }; f(4)").unwrap(), "Int(8)");
+    assert_eq!(run("f = () => { return; }; f()").unwrap(), "Null");
+    assert_eq!(run("f = () => { 1; }; f()").unwrap(), "Null");
+    assert_eq!(run("f = () => { 1 }; f()").unwrap(), "Int(1)");
+}
+
+#[test]
+fn return_from_nested_blocks() {
+    let code = "
+        find = (xs, y) => {
+            for x in xs {
+                while true {
+                    if x == y { return x * 100; }
+                    break;
+                }
+            }
+            -1
+        };
+        [find([1, 2, 3], 2), find([1, 2, 3], 5)]
+    ";
+    assert_eq!(run(code).unwrap(), "List([Int(200), Int(-1)])");
+}
+
+#[test]
+fn return_leaves_the_stack_clean() {
+    let code = "
+        f = x => { for i in 0..10 { for j in 0..10 { return x; } } };
+        for i in 0..1000 { f(i); }
+        f(1)
+    ";
+    assert_eq!(run(code).unwrap(), "Int(1)");
+}
+
+#[test]
+fn return_within_arguments() {
+    assert_eq!(run("id = x => x; f = () => { id(if true { return 3; }); 4 }; f()").unwrap(), "Int(3)");
+}
+
+#[test]
+fn return_outside_function() {
+    assert_matches!(
+        run("return 5;"),
+        Err(Error { error_type: ErrorType::CompilerError(CompilerErrorType::ReturnOutsideFunction), .. })
+    );
+}
+
+#[test]
+fn map_literals() {
+    assert_eq!(run("m = { \"a\": 1, b: 2 }; m[\"a\"] + m[\"b\"]").unwrap(), "Int(3)");
+    assert_eq!(run("{ b: 2, a: [1], }").unwrap(), "Map({\"a\": List([Int(1)]), \"b\": Int(2)})");
+    assert_eq!(run("{ a: 1, a: 2 }").unwrap(), "Map({\"a\": Int(2)})");
+    assert_eq!(run("m = {}; m").unwrap(), "Map({})");
+    assert_eq!(run("{ a: { b: 5 } }[\"a\"][\"b\"]").unwrap(), "Int(5)");
+}
+
+#[test]
+fn map_assignment() {
+    assert_eq!(run("m = { a: 1 }; m[\"b\"] = 2; m[\"a\"] += 10; m").unwrap(), "Map({\"a\": Int(11), \"b\": Int(2)})");
+    assert_eq!(run("m = { a: 1 }; n = m; n[\"a\"] = 5; m[\"a\"]").unwrap(), "Int(5)");
+}
+
+#[test]
+fn map_builtins() {
+    assert_eq!(run("keys({ b: 1, a: 2 })").unwrap(), "List([String(\"a\"), String(\"b\")])");
+    assert_eq!(run("m = { a: 1 }; [has(m, \"a\"), has(m, \"b\")]").unwrap(), "List([Bool(true), Bool(false)])");
+    assert_eq!(run("has([1, 2], 2.0)").unwrap(), "Bool(true)");
+}
+
+#[test]
+fn lambdas_tell_maps_and_blocks_apart() {
+    assert_eq!(run("f = x => { a: x }; f(1)").unwrap(), "Map({\"a\": Int(1)})");
+    assert_eq!(run("f = x => { x }; f(1)").unwrap(), "Int(1)");
+    assert_eq!(run("f = () => {}; f()").unwrap(), "Null");
+}
+
+#[test]
+fn map_errors() {
+    assert_matches!(
+        run("m = { a: 1 }; m[\"b\"]"),
+        Err(Error { error_type: ErrorType::VMError(VMErrorType::KeyNotFound { .. }), .. })
+    );
+    assert_matches!(
+        run("m = { a: 1 }; m[0]"),
+        Err(Error { error_type: ErrorType::VMError(VMErrorType::InvalidCast), .. })
+    );
+    assert_matches!(
+        run("keys([1])"),
+        Err(Error { error_type: ErrorType::VMError(VMErrorType::InvalidCast), offset: 0, width: 9, .. })
+    );
+}
+
+#[test]
+fn member_access() {
+    assert_eq!(run("m = { a: 1, b: { c: 2 } }; m.a + m.b.c").unwrap(), "Int(3)");
+    assert_eq!(run("m = { a: 1 }; m.b = 2; m.a += 10; m").unwrap(), "Map({\"a\": Int(11), \"b\": Int(2)})");
+    assert_eq!(run("m = { f: x => x * 2 }; m.f(3)").unwrap(), "Int(6)");
+    assert_eq!(run("m = { len: 5 }; m.len").unwrap(), "Int(5)");
+}
+
+#[test]
+fn method_calls() {
+    assert_eq!(run("\"héllo\".len()").unwrap(), "Int(5)");
+    assert_eq!(run("\" Ab \".trim().upper()").unwrap(), "String(\"AB\")");
+    assert_eq!(run("\"a,b\".split(\",\")").unwrap(), "List([String(\"a\"), String(\"b\")])");
+    assert_eq!(run("xs = [1]; xs.push(2); xs.push(3); [xs.pop(), xs.len(), xs.contains(1)]").unwrap(), "List([Int(3), Int(2), Bool(true)])");
+    assert_eq!(run("m = { a: 1, b: 2 }; m.remove(\"a\"); [m.keys(), m.has(\"a\"), m.len()]").unwrap(), "List([List([String(\"b\")]), Bool(false), Int(1)])");
+}
+
+#[test]
+fn postfix_chains() {
+    assert_eq!(run("xs = [x => x + 1]; xs[0](1)").unwrap(), "Int(2)");
+    assert_eq!(run("m = { f: x => [x, x * 2] }; m.f(2)[1]").unwrap(), "Int(4)");
+    assert_eq!(run("f = () => { a: [1, 2] }; f().a[1]").unwrap(), "Int(2)");
+}
+
+#[test]
+fn member_errors() {
+    assert_matches!(
+        run("\"abc\".nope()"),
+        Err(Error { error_type: ErrorType::VMError(VMErrorType::UnknownMember { .. }), .. })
+    );
+    assert_matches!(
+        run("[].pop()"),
+        Err(Error { error_type: ErrorType::VMError(VMErrorType::IndexOutOfBounds { .. }), .. })
+    );
+    assert_matches!(
+        run("[].len(1)"),
+        Err(Error { error_type: ErrorType::VMError(VMErrorType::MismatchedArgumentCount), .. })
+    );
+}
+
+#[test]
+fn string_operators() {
+    assert_eq!(run("\"ab\" + \"c\"").unwrap(), "String(\"abc\")");
+    assert_eq!(run("s = \"a\"; s += \"b\"; s").unwrap(), "String(\"ab\")");
+    assert_eq!(run("[\"ab\" * 2, 2 * \"c\", \"d\" * -1]").unwrap(), "List([String(\"abab\"), String(\"cc\"), String(\"\")])");
+    assert_matches!(
+        run("\"a\" - \"b\""),
+        Err(Error { error_type: ErrorType::VMError(VMErrorType::OperationNotSupported), .. })
+    );
+}
+
+#[test]
+fn huge_string_repetition_is_an_error() {
+    assert_matches!(
+        run("\"ab\" * 9223372036854775807"),
+        Err(Error { error_type: ErrorType::VMError(VMErrorType::StringTooLarge), offset: 5, width: 1, .. })
+    );
+    assert_matches!(
+        run("s = \"ab\"; s *= 100000000"),
+        Err(Error { error_type: ErrorType::VMError(VMErrorType::StringTooLarge), offset: 12, width: 2, .. })
+    );
+    assert_matches!(
+        run("s = \"a\" * 40000000; s + s"),
+        Err(Error { error_type: ErrorType::VMError(VMErrorType::StringTooLarge), .. })
+    );
+}
+
+#[test]
+fn string_indexing_counts_characters() {
+    assert_eq!(run("s = \"häj\"; [s[1], s[2]]").unwrap(), "List([String(\"ä\"), String(\"j\")])");
+    assert_eq!(run("\"åäö!\"[1:3]").unwrap(), "String(\"äö\")");
+    assert_eq!(run("\"abc\"[3:3]").unwrap(), "String(\"\")");
+    assert_matches!(
+        run("\"åäö\"[3]"),
+        Err(Error { error_type: ErrorType::VMError(VMErrorType::IndexOutOfBounds { list_size: 3, index: 3 }), .. })
+    );
+    assert_matches!(
+        run("\"abc\"[2:1]"),
+        Err(Error { error_type: ErrorType::VMError(VMErrorType::IndexOutOfBounds { list_size: 3, index: 1 }), .. })
+    );
+}
+
+#[test]
+fn string_interpolation() {
+    assert_eq!(run("name = \"you\"; \"Hello ${name}!\"").unwrap(), "String(\"Hello you!\")");
+    assert_eq!(run("\"${1 + 2} ${[1, \"a\"]} ${{ a: 1.0 }} ${null}\"").unwrap(), "String(\"3 [1, \\\"a\\\"] {\\\"a\\\": 1.0} null\")");
+    assert_eq!(run("x = 2; \"${ \"inner ${x * 2}\" }\"").unwrap(), "String(\"inner 4\")");
+    assert_eq!(run("\"\\${x} r\"").unwrap(), "String(\"${x} r\")");
+    assert_eq!(run("f = x => \"<${x}>\"; f(\"a\") + f(1)").unwrap(), "String(\"<a><1>\")");
+    assert_eq!(run("\"${r\"a\\\" + \"b\"}\"").unwrap(), "String(\"a\\\\b\")");
+    assert_eq!(run("\"<${r\"}${\"}>\"").unwrap(), "String(\"<}${>\")");
+}
+
+#[test]
+fn interpolation_errors_point_into_the_string() {
+    assert_matches!(
+        run("\"a ${[] - 1} b\""),
+        Err(Error { error_type: ErrorType::VMError(VMErrorType::OperationNotSupported), offset: 8, width: 1, .. })
+    );
+    assert_matches!(
+        run("x = \"${ ) }\""),
+        Err(Error { error_type: ErrorType::ParserError(_), offset: 8, .. })
+    );
+    assert_matches!(
+        run("\"${ 1 \""),
+        Err(Error { error_type: ErrorType::LexerError(LexerErrorType::UnexpectedEndOfString), .. })
+    );
+    assert_matches!(
+        run("\"${1 +}\""),
+        Err(Error { error_type: ErrorType::ParserError(ParserErrorType::UnexpectedToken), offset: 6, width: 1, .. })
+    );
+}
+
+#[test]
+fn block_comments_in_code() {
+    assert_eq!(run("f = x => /* double /* it */ */ x * 2; f(/* three */ 3) // done").unwrap(), "Int(6)");
+    assert_eq!(run("\"/* not a comment */\"").unwrap(), "String(\"/* not a comment */\")");
+}
+
+#[test]
+fn numeric_literal_forms() {
+    assert_eq!(run("0xff + 0b1 + 0o10 + 1_000").unwrap(), "Int(1264)");
+    assert_eq!(run("sum = 0; for i in 0..3 { sum += i; } sum * 1e2").unwrap(), "Float(300.0)");
+}
+
+#[test]
+fn integers_are_64_bit() {
+    assert_eq!(run("2147483647 + 1").unwrap(), "Int(2147483648)");
+    assert_eq!(run("2 ** 62").unwrap(), "Int(4611686018427387904)");
+    assert_eq!(run("9223372036854775807").unwrap(), "Int(9223372036854775807)");
+}
+
+#[test]
+fn integer_overflow_is_an_error() {
+    assert_matches!(
+        run("x = 9223372036854775807; x + 1"),
+        Err(Error { error_type: ErrorType::VMError(VMErrorType::IntegerOverflow), offset: 27, width: 1, .. })
+    );
+    assert_matches!(
+        run("2 ** 64"),
+        Err(Error { error_type: ErrorType::VMError(VMErrorType::IntegerOverflow), offset: 2, width: 2, .. })
+    );
+    assert_matches!(
+        run("x = -9223372036854775807 - 1; [x * -1]"),
+        Err(Error { error_type: ErrorType::VMError(VMErrorType::IntegerOverflow), .. })
+    );
+    assert_matches!(
+        run("x = -9223372036854775807 - 1; -x"),
+        Err(Error { error_type: ErrorType::VMError(VMErrorType::IntegerOverflow), offset: 30, width: 1, .. })
+    );
+    assert_matches!(
+        run("x = 3037000500; x *= x"),
+        Err(Error { error_type: ErrorType::VMError(VMErrorType::IntegerOverflow), .. })
+    );
+}
+
+#[test]
+fn division_by_zero_is_an_error() {
+    assert_matches!(
+        run("x = 0; 10 / x"),
+        Err(Error { error_type: ErrorType::VMError(VMErrorType::DivisionByZero), offset: 10, width: 1, .. })
+    );
+    assert_matches!(
+        run("10 % 0"),
+        Err(Error { error_type: ErrorType::VMError(VMErrorType::DivisionByZero), offset: 3, width: 1, .. })
+    );
+    assert_matches!(
+        run("x = 1; x /= 0"),
+        Err(Error { error_type: ErrorType::VMError(VMErrorType::DivisionByZero), offset: 9, width: 2, .. })
+    );
+}
+
+#[test]
+fn smallest_int_divided_by_minus_one() {
+    assert_matches!(
+        run("x = -9223372036854775807 - 1; x / -1"),
+        Err(Error { error_type: ErrorType::VMError(VMErrorType::IntegerOverflow), offset: 32, width: 1, .. })
+    );
+    assert_eq!(run("x = -9223372036854775807 - 1; x % -1").unwrap(), "Int(0)");
+}
+
+#[test]
+fn float_division_follows_ieee() {
+    assert_eq!(run("1.0 / 0").unwrap(), "Float(inf)");
+    assert_eq!(run("-1 / 0.0").unwrap(), "Float(-inf)");
+    assert_eq!(run("0.0 / 0.0").unwrap(), "Float(NaN)");
+    assert_eq!(run("1.0 % 0.0").unwrap(), "Float(NaN)");
+    assert_eq!(run("x = 0.0 / 0.0; [x == x, x != x, !x]").unwrap(), "List([Bool(false), Bool(true), Bool(false)])");
+}
+
+#[test]
+fn negative_indices() {
+    assert_eq!(run("xs = [1, 2, 3]; [xs[-1], xs[-3], \"abc\"[-2]]").unwrap(), "List([Int(3), Int(1), String(\"b\")])");
+    assert_eq!(run("xs = [1, 2, 3]; xs[-1] = 5; xs[-2] += 10; xs").unwrap(), "List([Int(1), Int(12), Int(5)])");
+    assert_matches!(
+        run("[1, 2][-3]"),
+        Err(Error { error_type: ErrorType::VMError(VMErrorType::IndexOutOfBounds { list_size: 2, index: -3 }), .. })
+    );
+}
+
+#[test]
+fn list_slices() {
+    assert_eq!(run("xs = [1, 2, 3, 4]; xs[1:3]").unwrap(), "List([Int(2), Int(3)])");
+    assert_eq!(run("xs = [1, 2, 3, 4]; [xs[:-1], xs[2:], xs[-2:-1]]").unwrap(), "List([List([Int(1), Int(2), Int(3)]), List([Int(3), Int(4)]), List([Int(3)])])");
+    assert_eq!(run("\"hello\"[:-1] + \"hello\"[:]").unwrap(), "String(\"hellhello\")");
+
+    // Slices are new lists
+    assert_eq!(run("xs = [1, 2]; ys = xs[:]; ys[0] = 5; xs").unwrap(), "List([Int(1), Int(2)])");
+}
+
+#[test]
+fn slice_errors() {
+    assert_matches!(
+        run("[1, 2][1:5]"),
+        Err(Error { error_type: ErrorType::VMError(VMErrorType::IndexOutOfBounds { list_size: 2, index: 5 }), offset: 0, width: 11, .. })
+    );
+    assert_matches!(
+        run("[1, 2][-3:]"),
+        Err(Error { error_type: ErrorType::VMError(VMErrorType::IndexOutOfBounds { list_size: 2, index: -3 }), .. })
+    );
+    assert_matches!(
+        run("{ a: 1 }[0:1]"),
+        Err(Error { error_type: ErrorType::VMError(VMErrorType::InvalidCast), .. })
+    );
+}
+
+#[test]
+fn destructuring_assignment() {
+    assert_eq!(run("[a, b, c] = [1, 2, 3]; [c, b, a]").unwrap(), "List([Int(3), Int(2), Int(1)])");
+    assert_eq!(run("xs = [1, 2, 3]; [head, ...rest] = xs; [head, rest]").unwrap(), "List([Int(1), List([Int(2), Int(3)])])");
+    assert_eq!(run("[a, ...rest] = [1]; rest").unwrap(), "List([])");
+    assert_eq!(run("[a, [b, c]] = [1, [2, 3]]; a + b + c").unwrap(), "Int(6)");
+    assert_eq!(run("a = 1; b = 2; [a, b] = [b, a]; [a, b]").unwrap(), "List([Int(2), Int(1)])");
+    assert_eq!(run("f = () => { [x] = [5]; }; x = 1; f(); x").unwrap(), "Int(5)");
+}
+
+#[test]
+fn destructuring_parameters() {
+    assert_eq!(run("f = ([a, b], c) => a * b + c; f([2, 3], 4)").unwrap(), "Int(10)");
+    assert_eq!(run("f = ([x, ...xs]) => xs; f([1, 2, 3])").unwrap(), "List([Int(2), Int(3)])");
+    assert_eq!(run("pairs = [[1, 2], [3, 4]]; sum = 0; add = ([a, b]) => a * b; for p in pairs { sum += add(p); } sum").unwrap(), "Int(14)");
+}
+
+#[test]
+fn destructuring_errors() {
+    assert_matches!(
+        run("[a, b] = [1, 2, 3]"),
+        Err(Error { error_type: ErrorType::VMError(VMErrorType::MismatchedPatternLength { expected: 2, found: 3 }), offset: 0, width: 6, .. })
+    );
+    assert_matches!(
+        run("[a, b, ...c] = [1]"),
+        Err(Error { error_type: ErrorType::VMError(VMErrorType::MismatchedPatternLength { expected: 2, found: 1 }), .. })
+    );
+    assert_matches!(
+        run("f = ([a, b]) => a; f([1])"),
+        Err(Error { error_type: ErrorType::VMError(VMErrorType::MismatchedPatternLength { expected: 2, found: 1 }), offset: 5, width: 6, .. })
+    );
+    assert_matches!(
+        run("[a, 1] = [1, 2]"),
+        Err(Error { error_type: ErrorType::ParserError(ParserErrorType::InvalidPattern), offset: 4, width: 1, .. })
+    );
+    assert_matches!(
+        run("[...a, b] = [1, 2]"),
+        Err(Error { error_type: ErrorType::ParserError(ParserErrorType::InvalidPattern), offset: 4, width: 1, .. })
+    );
+}
+
+#[test]
+fn default_parameters() {
+    assert_eq!(run("f = (a, b = 2) => a * b; [f(3), f(3, 4)]").unwrap(), "List([Int(6), Int(12)])");
+    assert_eq!(run("f = (a, b = a + 1) => [a, b]; f(1)").unwrap(), "List([Int(1), Int(2)])");
+    assert_eq!(run("f = ([a, b] = [1, 2]) => a + b; [f(), f([3, 4])]").unwrap(), "List([Int(3), Int(7)])");
+    assert_eq!(run("n = 0; next = () => n += 1; f = (a = next()) => a; f(); f(10); f(); n").unwrap(), "Int(2)");
+    assert_eq!(run("f = (a = if true { return 5 } else { 1 }) => a; [f(), f(2)]").unwrap(), "List([Int(5), Int(2)])");
+    assert_matches!(
+        run("for i in 0..3 { f = (a = if true { continue } else { 1 }) => a; print(f()); }"),
+        Err(Error { error_type: ErrorType::CompilerError(CompilerErrorType::ContinueOutsideLoop), .. })
+    );
+}
+
+#[test]
+fn rest_parameters() {
+    assert_eq!(run("f = (...args) => args; f(1, 2, 3)").unwrap(), "List([Int(1), Int(2), Int(3)])");
+    assert_eq!(run("f = (a, ...args) => [a, args]; f(1)").unwrap(), "List([Int(1), List([])])");
+    assert_eq!(run("f = (a, b = 2, ...args) => [a, b, args]; f(1, 5, 6, 7)").unwrap(), "List([Int(1), Int(5), List([Int(6), Int(7)])])");
+}
+
+#[test]
+fn named_arguments() {
+    assert_eq!(run("f = (a, b) => a - b; f(b: 1, a: 3)").unwrap(), "Int(2)");
+    assert_eq!(run("f = (a, b = 2, c = 3) => [a, b, c]; f(1, c: 4)").unwrap(), "List([Int(1), Int(2), Int(4)])");
+    assert_eq!(run("b = 5; f = (a, b) => a + b; f(1, b: b)").unwrap(), "Int(6)");
+}
+
+#[test]
+fn argument_errors() {
+    assert_matches!(
+        run("f = (a, b) => a; f(1)"),
+        Err(Error { error_type: ErrorType::VMError(VMErrorType::MissingArgument { ref parameter }), offset: 17, width: 4, .. }) if parameter == "b"
+    );
+    assert_matches!(
+        run("f = (a) => a; f(1, 2)"),
+        Err(Error { error_type: ErrorType::VMError(VMErrorType::UnexpectedArgument { index: 1 }), .. })
+    );
+    assert_matches!(
+        run("f = (a) => a; f(1, c: 2)"),
+        Err(Error { error_type: ErrorType::VMError(VMErrorType::UnknownParameter { ref parameter }), .. }) if parameter == "c"
+    );
+    assert_matches!(
+        run("f = (a) => a; f(1, a: 2)"),
+        Err(Error { error_type: ErrorType::VMError(VMErrorType::DuplicateArgument { ref parameter }), .. }) if parameter == "a"
+    );
+    assert_matches!(
+        run("print(value: 1)"),
+        Err(Error { error_type: ErrorType::VMError(VMErrorType::UnknownParameter { ref parameter }), .. }) if parameter == "value"
+    );
+    assert_matches!(
+        run("f = (a) => a; f(a: 1, 2)"),
+        Err(Error { error_type: ErrorType::ParserError(_), .. })
+    );
+}
+
+#[test]
+fn spread_in_lists() {
+    assert_eq!(run("a = [1, 2]; b = [5]; [...a, 3, 4, ...b]").unwrap(), "List([Int(1), Int(2), Int(3), Int(4), Int(5)])");
+    assert_eq!(run("a = []; [...a]").unwrap(), "List([])");
+    assert_eq!(run("a = [1]; b = [...a]; b.push(2); a").unwrap(), "List([Int(1)])");
+    assert_eq!(run("[...[1, 2], ...[3]]").unwrap(), "List([Int(1), Int(2), Int(3)])");
+}
+
+#[test]
+fn spread_in_function_calls() {
+    assert_eq!(run("f = (a, b, c) => [c, b, a]; args = [1, 2, 3]; f(...args)").unwrap(), "List([Int(3), Int(2), Int(1)])");
+    assert_eq!(run("f = (a, b, c) => a + b + c; f(1, ...[2], 3)").unwrap(), "Int(6)");
+    assert_eq!(run("f = (a, ...rest) => rest; f(...[1, 2, 3])").unwrap(), "List([Int(2), Int(3)])");
+    assert_eq!(run("f = (a, b = 2, c = 3) => [a, b, c]; f(...[1], c: 5)").unwrap(), "List([Int(1), Int(2), Int(5)])");
+    assert_eq!(run("xs = []; xs.push(...[1]); xs").unwrap(), "List([Int(1)])");
+}
+
+#[test]
+fn spread_errors() {
+    assert_matches!(
+        run("f = (a, b) => a; f(...[1])"),
+        Err(Error { error_type: ErrorType::VMError(VMErrorType::MissingArgument { ref parameter }), offset: 17, width: 9, .. }) if parameter == "b"
+    );
+    assert_matches!(
+        run("f = (a) => a; f(...[1, 2])"),
+        Err(Error { error_type: ErrorType::VMError(VMErrorType::UnexpectedArgument { index: 1 }), .. })
+    );
+    assert_matches!(
+        run("[].push(...[])"),
+        Err(Error { error_type: ErrorType::VMError(VMErrorType::MismatchedArgumentCount), .. })
+    );
+    assert_matches!(
+        run("[1, ...2]"),
+        Err(Error { error_type: ErrorType::VMError(VMErrorType::InvalidCast), offset: 4, width: 4, .. })
+    );
+    assert_matches!(
+        run("x = ...[1]"),
+        Err(Error { error_type: ErrorType::ParserError(_), .. })
+    );
+}
+
+#[test]
+fn let_and_const_declarations() {
+    assert_eq!(run("let a = 1; const b = 2; a = a + b; a").unwrap(), "Int(3)");
+    assert_eq!(run("let a; a").unwrap(), "Null");
+    assert_eq!(run("let [a, ...rest] = [1, 2, 3]; rest").unwrap(), "List([Int(2), Int(3)])");
+    assert_eq!(run("const xs = [1]; xs.push(2); xs[0] = 0; xs").unwrap(), "List([Int(0), Int(2)])");
+}
+
+#[test]
+fn declarations_are_block_scoped() {
+    assert_eq!(run("let x = 1; if x { let x = 2; x = 3; } x").unwrap(), "Int(1)");
+    assert_eq!(run("x = 1; { let x = 5; } x").unwrap(), "Int(1)");
+    assert_eq!(run("x = 1; { let y = 5; x = y; { let y = 6; } x += y; } x").unwrap(), "Int(10)");
+    assert_eq!(run("{ let y = 2; y * 3 }").unwrap(), "Int(6)");
+    assert_eq!(run("let x = 1; { let x = 2; x }").unwrap(), "Int(2)");
+    assert_eq!(run("y = if true { let z = 2; z }; y").unwrap(), "Int(2)");
+    assert_eq!(run("const c = 1; { const c = 2; c }").unwrap(), "Int(2)");
+    assert_eq!(run("{}").unwrap(), "Map({})");
+    assert_eq!(run("{ a: 1 }").unwrap(), "Map({\"a\": Int(1)})");
+    assert_matches!(
+        run_strict("{ let y = 1; } y = 2"),
+        Err(Error { error_type: ErrorType::ResolverError(ResolverErrorType::UndeclaredVariable { .. }), .. })
+    );
+    assert_eq!(run("x = 1; if x { let y = 2; x = y; } x").unwrap(), "Int(2)");
+    assert_eq!(run("let x = 1; let x = x + 1; x").unwrap(), "Int(2)");
+    assert_eq!(run("fs = []; for i in 0..3 { const j = i * 2; fs.push(() => j); } [fs[0](), fs[2]()]").unwrap(), "List([Int(0), Int(4)])");
+    assert_eq!(run("n = 0; while n < 3 { let m = n; n += 1; if m == 1 { continue; } } n").unwrap(), "Int(3)");
+    assert_eq!(run("n = 0; while 1 { if 1 { let m = n; if m == 2 { break; } } n += 1; } n").unwrap(), "Int(2)");
+}
+
+#[test]
+fn assigning_to_a_constant() {
+    assert_matches!(
+        run("const c = 1; c = 2"),
+        Err(Error { error_type: ErrorType::ResolverError(ResolverErrorType::AssignToConstant { ref identifier }), offset: 13, width: 1, .. }) if identifier == "c"
+    );
+    assert_matches!(
+        run("const c = 1; c += 2"),
+        Err(Error { error_type: ErrorType::ResolverError(ResolverErrorType::AssignToConstant { .. }), .. })
+    );
+    assert_matches!(
+        run("const c = 1; [a, c] = [1, 2]"),
+        Err(Error { error_type: ErrorType::ResolverError(ResolverErrorType::AssignToConstant { .. }), .. })
+    );
+    assert_matches!(
+        run("f = () => { c = 2; }; const c = 1; f()"),
+        Err(Error { error_type: ErrorType::ResolverError(ResolverErrorType::AssignToConstant { .. }), offset: 12, width: 1, .. })
+    );
+    assert_eq!(run("const c = 1; if c { let c = 2; c = 3; } c").unwrap(), "Int(1)");
+    assert_eq!(run("const c = 1; f = c => { c = 2; c }; f(0)").unwrap(), "Int(2)");
+    assert_matches!(
+        run("const c = 1; let c = 2; c = 3; c"),
+        Err(Error { error_type: ErrorType::ResolverError(ResolverErrorType::RedeclaredConstant { ref identifier }), offset: 13, width: 9, .. }) if identifier == "c"
+    );
+    assert_matches!(
+        run("const c = 1; const c = 5; c"),
+        Err(Error { error_type: ErrorType::ResolverError(ResolverErrorType::RedeclaredConstant { .. }), .. })
+    );
+    assert_matches!(
+        run("const c = 1; let [a, c] = [1, 2];"),
+        Err(Error { error_type: ErrorType::ResolverError(ResolverErrorType::RedeclaredConstant { .. }), .. })
+    );
+    assert_eq!(run("const c = 1; { const c = 2; } c").unwrap(), "Int(1)");
+    assert_eq!(run("let c = 1; const c = 2; c").unwrap(), "Int(2)");
+    assert_matches!(
+        run("const c;"),
+        Err(Error { error_type: ErrorType::ParserError(ParserErrorType::UnexpectedToken), .. })
+    );
+}
+
+#[test]
+fn constants_across_lines() {
+    let results = run_lines(&[
+        "f = () => { x = 5; };",
+        "const x = 1;",
+        "f()",
+        "x",
+        "const c = 1 / 0;",
+        "let c = 2;",
+        "c = 3; c"
+    ]);
+
+    assert_matches!(
+        results[2],
+        Err(Error { error_type: ErrorType::VMError(VMErrorType::AssignToConstant { ref identifier }), .. }) if identifier == "x"
+    );
+    assert_eq!(results[3].as_ref().unwrap(), "Int(1)");
+    assert_matches!(results[4], Err(Error { error_type: ErrorType::VMError(VMErrorType::DivisionByZero), .. }));
+    assert_eq!(results[6].as_ref().unwrap(), "Int(3)");
+}
+
+#[test]
+fn strict_mode_needs_declarations() {
+    assert_eq!(run_strict("let a = 1; a = 2; a").unwrap(), "Int(2)");
+    assert_eq!(run_strict("let f = (x, y = 1, ...r) => { x = y; for i in r { x += i; } x }; f(1, 2, 3)").unwrap(), "Int(5)");
+    assert_eq!(run_strict("let n = 0; let inc = () => n += 1; inc(); inc(); n").unwrap(), "Int(2)");
+    assert_matches!(
+        run_strict("let value = 1; valeu = 2"),
+        Err(Error { error_type: ErrorType::ResolverError(ResolverErrorType::UndeclaredVariable { ref identifier }), offset: 15, width: 5, .. }) if identifier == "valeu"
+    );
+    assert_matches!(
+        run_strict("if 1 { let a = 1; } a = 2"),
+        Err(Error { error_type: ErrorType::ResolverError(ResolverErrorType::UndeclaredVariable { .. }), .. })
+    );
+    assert_matches!(
+        run_strict("let f = () => { b = 1; }"),
+        Err(Error { error_type: ErrorType::ResolverError(ResolverErrorType::UndeclaredVariable { .. }), .. })
+    );
+}